@@ -0,0 +1,313 @@
+//! `#[derive(Binspect)]`, re-exported by `binspect` with the `derive` feature. See the
+//! documentation of `binspect::Binspect`.
+
+extern crate proc_macro;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{
+    parenthesized, parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, LitInt,
+    Member, Path, Token, Type,
+};
+
+#[proc_macro_derive(Binspect, attributes(binspect))]
+pub fn derive_binspect(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// An argument of a `#[binspect(..)]` attribute.
+enum Arg {
+    /// `skip`, `redact` or `generate_tests`.
+    Flag(Ident),
+    /// `with = path`.
+    With(Path),
+    /// `offset = 4`, `size = 8` or `align = 4`.
+    Int(Ident, LitInt),
+    /// `generate_tests(size = 8, align = 4)`.
+    List(Ident, Punctuated<Arg, Token![,]>),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Arg> {
+        let name: Ident = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            if name == "with" {
+                Ok(Arg::With(input.parse()?))
+            } else {
+                Ok(Arg::Int(name, input.parse()?))
+            }
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Ok(Arg::List(name, content.parse_terminated(Arg::parse)?))
+        } else {
+            Ok(Arg::Flag(name))
+        }
+    }
+}
+
+fn args(attrs: &[Attribute]) -> syn::Result<Vec<Arg>> {
+    let mut args = vec![];
+    for attr in attrs.iter().filter(|a| a.path.is_ident("binspect")) {
+        let list = attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)?;
+        args.extend(list);
+    }
+    Ok(args)
+}
+
+/// Annotations of a field.
+#[derive(Default)]
+struct FieldArgs {
+    skip: bool,
+    redact: bool,
+    with: Option<Path>,
+    offset: Option<LitInt>,
+}
+
+fn field_args(attrs: &[Attribute]) -> syn::Result<FieldArgs> {
+    let mut field = FieldArgs::default();
+    for arg in args(attrs)? {
+        match arg {
+            Arg::Flag(ref name) if name == "skip" => field.skip = true,
+            Arg::Flag(ref name) if name == "redact" => field.redact = true,
+            Arg::With(path) => field.with = Some(path),
+            Arg::Int(ref name, ref value) if name == "offset" => field.offset = Some(value.clone()),
+            Arg::Flag(name) | Arg::Int(name, _) | Arg::List(name, _) => {
+                return Err(Error::new(name.span(), "unknown field attribute"));
+            }
+        }
+    }
+    Ok(field)
+}
+
+/// The expected layout of `#[binspect(generate_tests(size = .., align = ..))]`.
+struct Tests {
+    size: LitInt,
+    align: LitInt,
+}
+
+fn tests(attrs: &[Attribute]) -> syn::Result<Option<Tests>> {
+    let mut tests = None;
+    for arg in args(attrs)? {
+        match arg {
+            Arg::List(ref name, ref list) if name == "generate_tests" => {
+                let (mut size, mut align) = (None, None);
+                for arg in list {
+                    match arg {
+                        Arg::Int(name, value) if name == "size" => size = Some(value.clone()),
+                        Arg::Int(name, value) if name == "align" => align = Some(value.clone()),
+                        _ => return Err(Error::new(name.span(), "expected `size` and `align`")),
+                    }
+                }
+                match (size, align) {
+                    (Some(size), Some(align)) => tests = Some(Tests { size, align }),
+                    _ => return Err(Error::new(name.span(), "expected `size` and `align`")),
+                }
+            }
+            Arg::Flag(name) | Arg::Int(name, _) | Arg::List(name, _) => {
+                return Err(Error::new(name.span(), "unknown type attribute"));
+            }
+            Arg::With(path) => return Err(Error::new_spanned(path, "unknown type attribute")),
+        }
+    }
+    Ok(tests)
+}
+
+/// Returns the encoding of a field from its annotations or its type.
+fn encoding(ty: &Type, args: &FieldArgs) -> TokenStream {
+    if args.redact {
+        return quote!(::binspect::schema::Encoding::Redacted);
+    }
+    if args.skip {
+        return quote!(::binspect::schema::Encoding::Skipped);
+    }
+    if let Some(path) = &args.with {
+        let name = path
+            .segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::");
+        return quote! {
+            ::binspect::schema::Encoding::Custom(::binspect::schema::Decoder::new(#name, #path))
+        };
+    }
+    let name = match ty {
+        Type::Path(p) if p.qself.is_none() => p.path.get_ident().map(|i| i.to_string()),
+        _ => None,
+    };
+    let endian = quote!(::binspect::schema::Endian::native());
+    match name.as_deref() {
+        Some("u8") | Some("u16") | Some("u32") | Some("u64") | Some("usize") => {
+            quote!(::binspect::schema::Encoding::Unsigned(#endian))
+        }
+        Some("i8") | Some("i16") | Some("i32") | Some("i64") | Some("isize") => {
+            quote!(::binspect::schema::Encoding::Signed(#endian))
+        }
+        Some("f32") | Some("f64") => quote!(::binspect::schema::Encoding::Float(#endian)),
+        _ => quote!(::binspect::schema::Encoding::Bytes),
+    }
+}
+
+/// Returns statements adding the fields bound to `bindings` to `schema`, named with `prefix`.
+fn push_fields(
+    fields: &Fields,
+    bindings: &[TokenStream],
+    prefix: &str,
+) -> syn::Result<TokenStream> {
+    let mut out = TokenStream::new();
+    for (i, (field, binding)) in fields.iter().zip(bindings).enumerate() {
+        let args = field_args(&field.attrs)?;
+        let name = match &field.ident {
+            Some(ident) => format!("{}{}", prefix, ident),
+            None => format!("{}{}", prefix, i),
+        };
+        let encoding = encoding(&field.ty, &args);
+        out.extend(quote! {
+            {
+                let f = #binding;
+                schema = schema.field(
+                    #name,
+                    f as *const _ as *const u8 as usize - base,
+                    ::core::mem::size_of_val(f),
+                    #encoding,
+                );
+            }
+        });
+    }
+    Ok(out)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let bindings: Vec<_> = data
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    let member = match &f.ident {
+                        Some(ident) => Member::Named(ident.clone()),
+                        None => Member::Unnamed(i.into()),
+                    };
+                    quote!(&self.#member)
+                })
+                .collect();
+            push_fields(&data.fields, &bindings, "")?
+        }
+        Data::Enum(data) => {
+            let mut arms = TokenStream::new();
+            for variant in &data.variants {
+                for field in &variant.fields {
+                    if let Some(offset) = field_args(&field.attrs)?.offset {
+                        return Err(Error::new_spanned(
+                            offset,
+                            "`offset` is not supported on the fields of enum variants",
+                        ));
+                    }
+                }
+                let ident = &variant.ident;
+                let bindings: Vec<_> = (0..variant.fields.len())
+                    .map(|i| format_ident!("f{}", i))
+                    .collect();
+                let pattern = match &variant.fields {
+                    Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|f| &f.ident);
+                        quote!(#name::#ident { #(#names: #bindings),* })
+                    }
+                    Fields::Unnamed(_) => quote!(#name::#ident(#(#bindings),*)),
+                    Fields::Unit => quote!(#name::#ident),
+                };
+                let bindings: Vec<_> = bindings.iter().map(|b| quote!(#b)).collect();
+                let push = push_fields(&variant.fields, &bindings, &format!("{}.", ident))?;
+                arms.extend(quote!(#pattern => { #push }));
+            }
+            quote! {
+                #[allow(unreachable_code)]
+                match self {
+                    #arms
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "#[derive(Binspect)] does not support unions",
+            ))
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut out = quote! {
+        impl #impl_generics ::binspect::Binspect for #name #ty_generics #where_clause {
+            fn schema(&self) -> ::binspect::schema::Schema {
+                let base = self as *const Self as *const u8 as usize;
+                let mut schema = ::binspect::schema::Schema::new();
+                #body
+                schema
+            }
+        }
+    };
+    if let Some(tests) = tests(&input.attrs)? {
+        out.extend(expand_tests(input, &tests)?);
+    }
+    Ok(out)
+}
+
+fn expand_tests(input: &DeriveInput, tests: &Tests) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "generate_tests is not supported on generic types",
+        ));
+    }
+    let mut offsets = TokenStream::new();
+    if let Data::Struct(data) = &input.data {
+        for (i, field) in data.fields.iter().enumerate() {
+            if let Some(offset) = field_args(&field.attrs)?.offset {
+                let member = match &field.ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(i.into()),
+                };
+                offsets.extend(quote! {
+                    assert_eq!(
+                        unsafe { ::core::ptr::addr_of!((*base).#member) } as *const u8 as usize
+                            - base as *const u8 as usize,
+                        #offset,
+                        "offset of {}.{} changed",
+                        stringify!(#name),
+                        stringify!(#member),
+                    );
+                });
+            }
+        }
+    }
+    if !offsets.is_empty() {
+        // The offsets are measured on an uninitialized value without creating references to it.
+        offsets = quote! {
+            let value = ::core::mem::MaybeUninit::<#name>::uninit();
+            let base = value.as_ptr();
+            #offsets
+        };
+    }
+    // The test is placed next to the type rather than in a module, so that it names the type in
+    // the scope it is declared in.
+    let test = Ident::new(&format!("__binspect_layout_{}", name), Span::call_site());
+    let (size, align) = (&tests.size, &tests.align);
+    Ok(quote! {
+        #[cfg(test)]
+        #[test]
+        #[allow(non_snake_case)]
+        fn #test() {
+            ::binspect::assert_layout!(#name, size = #size, align = #align);
+            #offsets
+        }
+    })
+}
@@ -326,7 +326,7 @@ code_fence!(BOXES, {
         binspect!(s);
         let p = Box::into_raw(s);
         binspect!(*unsafe { &*p });
-        unsafe { Box::from_raw(p) };
+        drop(unsafe { Box::from_raw(p) });
         let t: Box<dyn T1> = Box::new(S4 {
             x: 0x11_u8,
             y: 0x2222_u16,
@@ -335,7 +335,7 @@ code_fence!(BOXES, {
         binspect!(t);
         let p = Box::into_raw(t);
         binspect!(*unsafe { &*p });
-        unsafe { Box::from_raw(p) };
+        drop(unsafe { Box::from_raw(p) });
     }
 });
 
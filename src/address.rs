@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use crate::Options;
+
+/// A callback writing an address, used by [`AddressFormat::Custom`].
+pub type FormatAddress = Arc<dyn Fn(usize, &mut fmt::Formatter) -> fmt::Result + Send + Sync>;
+
+/// How addresses are rendered in headers and, with [`Options::absolute`], at the start of lines.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, AddressFormat, Options};
+/// let v = [0u8; 4];
+/// let base = &v as *const _ as usize - 0x10;
+/// let options = Options {
+///     address: AddressFormat::Relative(vec![("buf".to_owned(), base)]),
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, v).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().starts_with("-----+ buf+0x10: [u8; 4] = v\n"));
+/// ```
+///
+/// Addresses of statics relative to the executable are stable across runs:
+///
+/// ```
+/// # use binspect::{write_binspect_with, AddressFormat, Options};
+/// static MAGIC: [u8; 4] = *b"\x7fELF";
+///
+/// let options = Options {
+///     address: AddressFormat::Module,
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, MAGIC).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// if cfg!(unix) {
+///     assert!(!out.starts_with("-----+ 0x"));
+///     assert!(out.contains("+0x"));
+/// }
+/// ```
+#[derive(Clone)]
+pub enum AddressFormat {
+    /// The full pointer, e.g. `0x7ffce3c8f7a0`.
+    Full,
+    /// The low 16 bits, e.g. `0xf7a0`, which is usually enough to tell objects apart.
+    Low16,
+    /// The offset from the nearest of the named bases at or below the address, e.g. `libfoo+0x1a40`
+    /// for the base of a module or `parse+0x2c` for the address of a symbol. Addresses below all of
+    /// the bases are rendered in full.
+    Relative(Vec<(String, usize)>),
+    /// The offset from the base of the executable or the shared object containing the address,
+    /// as reported by the dynamic loader, e.g. `libfoo.so+0x1a40`.
+    ///
+    /// For position-independent executables and shared objects, this matches the addresses printed
+    /// by `objdump` and `nm` and is stable across runs despite ASLR. Addresses outside any loaded
+    /// object, e.g. of the stack or the heap, and all addresses on platforms without `dladdr` are
+    /// rendered in full.
+    Module,
+    /// The address as an offset zero-padded to 8 hex digits, e.g. `0x0010fff8`, as wasm debuggers
+    /// show offsets of linear memory. This is the default on `wasm32`.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, AddressFormat, Options};
+    /// let v = 1u8;
+    /// let options = Options {
+    ///     address: AddressFormat::Offset,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, v).unwrap();
+    /// let header = format!("-----+ {:#010x}: u8 = v\n", &v as *const u8 as usize);
+    /// assert!(String::from_utf8(buf).unwrap().starts_with(&header));
+    /// ```
+    Offset,
+    /// A fixed placeholder, `0xXXXXXXXXXXXX`, so that dumps are the same in every run despite ASLR,
+    /// e.g. in documentation and golden tests. Lines start with offsets even with
+    /// [`Options::absolute`].
+    ///
+    /// Unlike [`Options::placeholders`], pointer-like bytes are still shown.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, AddressFormat, Options};
+    /// let v = [1u8, 2];
+    /// let options = Options {
+    ///     address: AddressFormat::Scrubbed,
+    ///     absolute: true,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, v).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "0xXXXXXXXXXXXX : [u8; 2] = v\n0000 | 01 02\n"
+    /// );
+    /// ```
+    Scrubbed,
+    /// An id per distinct address in the order the addresses are first rendered with these
+    /// [`SequentialIds`], e.g. `#1`, so that dumps are the same in every run while still telling
+    /// whether two dumps show the same object. Lines start with offsets even with
+    /// [`Options::absolute`].
+    ///
+    /// The ids are shared by the clones of the options, and numbered anew by other options, so
+    /// that tests running in parallel with their own options get the same ids in every run.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, AddressFormat, Options, SequentialIds};
+    /// let (a, b) = (1u8, 2u8);
+    /// let options = Options {
+    ///     address: AddressFormat::Sequential(SequentialIds::new()),
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, a; b).unwrap();
+    /// write_binspect_with!(&mut buf, &options.clone(), a).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "-----+ #1: u8 = a\n0000 | 01\n\
+    ///      -----+ #2: u8 = b\n0000 | 02\n\
+    ///      -----+ #1: u8 = a\n0000 | 01\n"
+    /// );
+    ///
+    /// let other = Options {
+    ///     address: AddressFormat::Sequential(SequentialIds::new()),
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &other, b).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().starts_with("-----+ #1: u8 = b\n"));
+    /// ```
+    Sequential(SequentialIds),
+    /// A custom format.
+    Custom(FormatAddress),
+}
+
+impl Default for AddressFormat {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default() -> AddressFormat {
+        AddressFormat::Full
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn default() -> AddressFormat {
+        AddressFormat::Offset
+    }
+}
+
+impl fmt::Debug for AddressFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressFormat::Full => f.write_str("Full"),
+            AddressFormat::Low16 => f.write_str("Low16"),
+            AddressFormat::Relative(bases) => f.debug_tuple("Relative").field(bases).finish(),
+            AddressFormat::Module => f.write_str("Module"),
+            AddressFormat::Offset => f.write_str("Offset"),
+            AddressFormat::Scrubbed => f.write_str("Scrubbed"),
+            AddressFormat::Sequential(ids) => f.debug_tuple("Sequential").field(ids).finish(),
+            AddressFormat::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl AddressFormat {
+    /// Returns whether addresses are replaced, so that lines should start with offsets.
+    pub(crate) fn is_scrubbed(&self) -> bool {
+        matches!(self, AddressFormat::Scrubbed | AddressFormat::Sequential(_))
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, p: *const u8) -> fmt::Result {
+        let addr = p as usize;
+        match self {
+            AddressFormat::Full => fmt::Pointer::fmt(&p, f),
+            AddressFormat::Low16 => write!(f, "{:#06x}", addr & 0xffff),
+            AddressFormat::Relative(bases) => {
+                match bases
+                    .iter()
+                    .filter(|(_, base)| *base <= addr)
+                    .max_by_key(|(_, base)| *base)
+                {
+                    Some((name, base)) => write!(f, "{}+{:#x}", name, addr - base),
+                    None => fmt::Pointer::fmt(&p, f),
+                }
+            }
+            AddressFormat::Module => match module_of(p) {
+                Some((name, base)) => write!(f, "{}+{:#x}", name, addr - base),
+                None => fmt::Pointer::fmt(&p, f),
+            },
+            AddressFormat::Offset => write!(f, "{:#010x}", addr),
+            AddressFormat::Scrubbed => f.write_str("0xXXXXXXXXXXXX"),
+            AddressFormat::Sequential(ids) => write!(f, "#{}", ids.id(addr)),
+            AddressFormat::Custom(format) => format(addr, f),
+        }
+    }
+}
+
+/// The ids of the addresses rendered with [`AddressFormat::Sequential`], in the order they were
+/// first rendered. Clones share the ids.
+#[derive(Clone, Default)]
+pub struct SequentialIds(Arc<Mutex<HashMap<usize, usize>>>);
+
+impl SequentialIds {
+    /// Returns ids which start again at `#1`.
+    pub fn new() -> SequentialIds {
+        SequentialIds::default()
+    }
+
+    /// Returns the id of `addr`, assigning the next one to new addresses.
+    fn id(&self, addr: usize) -> usize {
+        let mut ids = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let next = ids.len() + 1;
+        *ids.entry(addr).or_insert(next)
+    }
+}
+
+impl fmt::Debug for SequentialIds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.0.lock().map_or(0, |ids| ids.len());
+        write!(f, "SequentialIds({} addresses)", n)
+    }
+}
+
+/// Returns the file name and the base address of the loaded object containing `p`.
+#[cfg(unix)]
+fn module_of(p: *const u8) -> Option<(&'static str, usize)> {
+    use std::ffi::CStr;
+    use std::os::raw::{c_char, c_int, c_void};
+
+    #[repr(C)]
+    struct DlInfo {
+        dli_fname: *const c_char,
+        dli_fbase: *mut c_void,
+        dli_sname: *const c_char,
+        dli_saddr: *mut c_void,
+    }
+
+    #[cfg_attr(all(target_os = "linux", target_env = "gnu"), link(name = "dl"))]
+    extern "C" {
+        fn dladdr(addr: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    let mut info = DlInfo {
+        dli_fname: std::ptr::null(),
+        dli_fbase: std::ptr::null_mut(),
+        dli_sname: std::ptr::null(),
+        dli_saddr: std::ptr::null_mut(),
+    };
+    if unsafe { dladdr(p as *const c_void, &mut info) } == 0 || info.dli_fbase.is_null() {
+        return None;
+    }
+    // The path is owned by the loader and stays valid while the object is loaded.
+    let path = if info.dli_fname.is_null() {
+        ""
+    } else {
+        unsafe { CStr::from_ptr(info.dli_fname) }
+            .to_str()
+            .unwrap_or("")
+    };
+    let name = path.rsplit('/').next().unwrap_or(path);
+    Some((name, info.dli_fbase as usize))
+}
+
+#[cfg(not(unix))]
+fn module_of(_p: *const u8) -> Option<(&'static str, usize)> {
+    None
+}
+
+/// A scheme of tagged pointers, whose lowest and highest bits hold a tag instead of the address.
+///
+/// With [`Options::tags`], each aligned word of a dump which looks like a pointer once its tag is
+/// cleared is shown at the end of its line, e.g. `-> 0x55d4c2a0 tag 0x1`.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options, TagScheme};
+/// let p = Box::into_raw(Box::new(5u64));
+/// let tagged = p as usize | 0b101;
+///
+/// let options = Options {
+///     tags: Some(TagScheme::low(3)),
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, tagged).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.ends_with(&format!("  -> {:p} tag 0x5\n", p)));
+/// # drop(unsafe { Box::from_raw(p) });
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TagScheme {
+    /// The number of lowest bits holding a tag, e.g. 3 for 8-byte aligned pointers.
+    pub low_bits: u32,
+    /// The number of highest bits holding a tag, e.g. 16 for NaN boxing on 64-bit platforms.
+    pub high_bits: u32,
+}
+
+impl TagScheme {
+    /// Creates a scheme with a tag in the `bits` lowest bits.
+    pub fn low(bits: u32) -> TagScheme {
+        TagScheme {
+            low_bits: bits,
+            high_bits: 0,
+        }
+    }
+
+    /// Creates a scheme with a tag in the `bits` highest bits.
+    pub fn high(bits: u32) -> TagScheme {
+        TagScheme {
+            low_bits: 0,
+            high_bits: bits,
+        }
+    }
+
+    /// Splits a word into the address and the tag. The tag is the highest bits followed by the
+    /// lowest bits.
+    ///
+    /// Bit counts are clamped to the width of a pointer, the lowest bits first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use binspect::TagScheme;
+    /// assert_eq!(TagScheme::low(3).decode(0x1005), (0x1000, 0x5));
+    /// assert_eq!(TagScheme::low(200).decode(0x1005), (0, 0x1005));
+    /// let all = TagScheme {
+    ///     low_bits: 200,
+    ///     high_bits: 200,
+    /// };
+    /// assert_eq!(all.decode(0x1005), (0, 0x1005));
+    /// ```
+    pub fn decode(&self, word: usize) -> (usize, usize) {
+        let bits = mem::size_of::<usize>() as u32 * 8;
+        let low_mask = mask(self.low_bits.min(bits));
+        let high_bits = self.high_bits.min(bits - self.low_bits.min(bits));
+        let high_shift = bits - high_bits;
+        let high = if high_bits == 0 {
+            0
+        } else {
+            word >> high_shift
+        };
+        let address = word & !low_mask & mask(high_shift);
+        let high = high.checked_shl(self.low_bits).unwrap_or(0);
+        (address, high | word & low_mask)
+    }
+}
+
+/// Returns a mask of the `bits` lowest bits.
+fn mask(bits: u32) -> usize {
+    if bits >= mem::size_of::<usize>() as u32 * 8 {
+        usize::max_value()
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// An address rendered according to the options.
+pub(crate) struct Address<'a>(pub(crate) *const u8, pub(crate) &'a Options);
+
+impl<'a> fmt::Display for Address<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.1.placeholders {
+            f.write_str("[addr]")
+        } else {
+            self.1.address.write(f, self.0)
+        }
+    }
+}
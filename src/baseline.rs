@@ -0,0 +1,138 @@
+use std::any::type_name;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::diff::{write_bit_diff, write_diff};
+use crate::Record;
+
+/// Returns the directory of baseline files, `BINSPECT_BASELINE_DIR` or `target/binspect-baselines`.
+pub fn baseline_dir() -> PathBuf {
+    env::var_os("BINSPECT_BASELINE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target").join("binspect-baselines"))
+}
+
+#[doc(hidden)]
+pub fn write_baseline_internal<W: Write, T: ?Sized>(
+    mut w: W,
+    record: &Record<T>,
+    name: &str,
+) -> Result<(), io::Error> {
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(|c| c == '/' || c == '\\' || c == ':' || c == '\0')
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid baseline name {:?}", name),
+        ));
+    }
+    let path = baseline_dir().join(format!("{}.bin", name));
+    let update = env::var_os("BINSPECT_UPDATE_BASELINE").map_or(false, |v| v != "0");
+    write!(
+        w,
+        "-----+ {:p}: {} = {} ",
+        record.reference,
+        type_name::<T>(),
+        record.source
+    )?;
+    let old = match fs::read(&path) {
+        Ok(old) if !update => old,
+        Ok(_) => {
+            fs::write(&path, record.bytes)?;
+            return writeln!(w, "(baseline {:?} updated)", name);
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            fs::create_dir_all(baseline_dir())?;
+            fs::write(&path, record.bytes)?;
+            return writeln!(w, "(baseline {:?} created)", name);
+        }
+        Err(e) => return Err(e),
+    };
+    if old == record.bytes {
+        return writeln!(w, "(same as baseline {:?})", name);
+    }
+    writeln!(w, "(differs from baseline {:?})", name)?;
+    if old.len() != record.bytes.len() {
+        writeln!(w, "     ! size {} -> {}", old.len(), record.bytes.len())?;
+    }
+    write_diff(&mut w, &old, record.bytes)?;
+    if env::var_os("BINSPECT_BIT_DIFF").map_or(false, |v| v != "0") {
+        write_bit_diff(&mut w, &old, record.bytes)?;
+    }
+    Ok(())
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn print_baseline_internal<T: ?Sized>(record: &Record<T>, name: &str) {
+    write_baseline_internal(crate::capture::output(io::stdout()), record, name).unwrap()
+}
+
+/// Compares the bytes of an object with a baseline saved by a previous run and prints the
+/// differences to stdout.
+///
+/// The first run saves the bytes to `<name>.bin` in [`baseline_dir`]; later runs print only the
+/// byte ranges which changed since then, so the bytes of a type can be compared across runs and
+/// commits. Set `BINSPECT_UPDATE_BASELINE=1` to overwrite existing baselines, and
+/// `BINSPECT_BIT_DIFF=1` to list the flipped bits as [`write_bit_diff`](crate::write_bit_diff)
+/// does.
+///
+/// Names must not be empty, `.` or `..`, nor contain `/`, `\`, `:` or NUL, so that baselines
+/// stay in [`baseline_dir`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{baseline_dir, binspect_baseline};
+/// let x = (1u32, 2u32);
+/// binspect_baseline!(x, "pair");
+/// binspect_baseline!(x, "pair");
+/// # std::fs::remove_file(baseline_dir().join("pair.bin")).unwrap();
+/// ```
+///
+/// An example of output of a later run where the second field became `3`:
+///
+/// ```text
+/// -----+ 0x7ffd4c1e66b8: (u32, u32) = x (differs from baseline "pair")
+/// 0004 | - 02
+/// 0004 | + 03
+/// ```
+#[macro_export]
+macro_rules! binspect_baseline {
+    ($v: expr, $name: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_baseline_internal(&$crate::record!(t, $v, bs, true), $name);
+    }};
+}
+
+/// Writes the differences between an object and its baseline to [`std::io::Write`] like
+/// [`binspect_baseline!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{baseline_dir, write_binspect_baseline};
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_baseline!(&mut buf, [1u8, 2, 3, 4], "doc-array").unwrap();
+/// write_binspect_baseline!(&mut buf, [1u8, 2, 5, 4], "doc-array").unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains("(baseline \"doc-array\" created)\n"));
+/// assert!(out.ends_with("(differs from baseline \"doc-array\")\n0002 | - 03\n0002 | + 05\n"));
+/// # std::fs::remove_file(baseline_dir().join("doc-array.bin")).unwrap();
+///
+/// let e = write_binspect_baseline!(std::io::sink(), [0u8; 4], "../escape").unwrap_err();
+/// assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+/// ```
+#[macro_export]
+macro_rules! write_binspect_baseline {
+    ($w: expr, $v: expr, $name: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_baseline_internal($w, &$crate::record!(t, $v, bs, true), $name)
+    }};
+}
@@ -0,0 +1,52 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::{is_masked, is_unreadable, write_header, DumpInfo, Options};
+
+/// Number of cells per line if the width is not given.
+const DEFAULT_WIDTH: usize = 64;
+
+/// Writes a dump as a bitmap, each bit as a cell, least significant bit of each byte first.
+pub(crate) fn write_bitmap<W: Write>(
+    mut w: W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+    width: Option<usize>,
+) -> Result<(), io::Error> {
+    write_header(&mut w, info, options)?;
+    let width = width.unwrap_or(DEFAULT_WIDTH).max(1);
+    let bits = bytes.len() * 8;
+    let mut set = 0;
+    let mut start = 0;
+    while start < bits {
+        write!(w, "{:04x} |", start)?;
+        for i in start..(start + width).min(bits) {
+            if i == start || i % 8 == 0 {
+                write!(w, " ")?;
+            }
+            let byte = i / 8;
+            let cell = if is_unreadable(unreadable, byte)
+                || options.placeholders && is_masked(bytes, byte)
+            {
+                '?'
+            } else {
+                let x = match &options.mask {
+                    Some(mask) => mask.apply(byte, bytes[byte]),
+                    None => bytes[byte],
+                };
+                if x >> (i % 8) & 1 == 1 {
+                    set += 1;
+                    '#'
+                } else {
+                    '.'
+                }
+            };
+            write!(w, "{}", cell)?;
+        }
+        writeln!(w)?;
+        start += width;
+    }
+    writeln!(w, "     + {} of {} bits set", set, bits)
+}
@@ -0,0 +1,99 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::{
+    is_masked, is_unreadable, line_address, write_header, write_lines_with, DumpInfo, Options,
+    WIDTH,
+};
+
+/// Number of glyphs per line.
+const GLYPHS: usize = 64;
+
+/// Bits of the dots of a braille glyph for 8 consecutive bytes, down the left column and then
+/// down the right column.
+const DOTS: [u32; 8] = [0x01, 0x02, 0x04, 0x40, 0x08, 0x10, 0x20, 0x80];
+
+/// Writes a dump as braille glyphs, each dot set for a byte above `threshold`.
+pub(crate) fn write_braille<W: Write>(
+    mut w: W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+    threshold: u8,
+) -> Result<(), io::Error> {
+    write_header(&mut w, info, options)?;
+    let mut above = 0;
+    for (r, line) in bytes.chunks(GLYPHS * DOTS.len()).enumerate() {
+        let start = r * GLYPHS * DOTS.len();
+        write!(w, "{:04x} | ", start)?;
+        for (g, glyph) in line.chunks(DOTS.len()).enumerate() {
+            let mut bits = 0;
+            for (k, &x) in glyph.iter().enumerate() {
+                let i = start + g * DOTS.len() + k;
+                if is_unreadable(unreadable, i) || options.placeholders && is_masked(bytes, i) {
+                    continue;
+                }
+                let x = match &options.mask {
+                    Some(mask) => mask.apply(i, x),
+                    None => x,
+                };
+                if x > threshold {
+                    bits |= DOTS[k];
+                    above += 1;
+                }
+            }
+            // Braille patterns are contiguous from U+2800.
+            write!(w, "{}", std::char::from_u32(0x2800 + bits).unwrap())?;
+        }
+        writeln!(w)?;
+        if let Some(zoom) = &options.zoom {
+            let first = zoom.start.max(start).saturating_sub(start) / DOTS.len();
+            let last = (zoom.end.min(start + line.len()).saturating_sub(start) + DOTS.len() - 1)
+                / DOTS.len();
+            if first < last {
+                writeln!(
+                    w,
+                    "     | {:first$}{:^<n$}",
+                    "",
+                    "",
+                    first = first,
+                    n = last - first
+                )?;
+            }
+        }
+    }
+    writeln!(
+        w,
+        "     + {} of {} bytes above {:#04x}",
+        above,
+        bytes.len(),
+        threshold
+    )?;
+    match &options.zoom {
+        Some(zoom) => write_zoom(&mut w, info, bytes, options, unreadable, zoom),
+        None => Ok(()),
+    }
+}
+
+/// Writes the full lines of the `zoom` range of `bytes`, starting at a multiple of [`WIDTH`].
+fn write_zoom<W: Write>(
+    w: &mut W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+    zoom: &Range<usize>,
+) -> Result<(), io::Error> {
+    let end = zoom.end.min(bytes.len());
+    let start = (zoom.start / WIDTH * WIDTH).min(end);
+    writeln!(w, "     + zoom {:04x}..{:04x}", zoom.start, zoom.end)?;
+    let unreadable: Vec<Range<usize>> = unreadable
+        .iter()
+        .filter(|r| r.end > start)
+        .map(|r| r.start.saturating_sub(start)..r.end - start)
+        .collect();
+    let address = line_address(info, options).map(|p| p.wrapping_add(start));
+    let bytes = &bytes[start..end];
+    write_lines_with(w, bytes, start, address, options, &unreadable, info.schema)
+}
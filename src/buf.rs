@@ -0,0 +1,121 @@
+use std::io::{self, Cursor, Write};
+
+use ::bytes::{Buf, Bytes, BytesMut};
+
+use crate::{write_internal, write_lines, Options, Record};
+
+/// Buffers of the [`bytes`](::bytes) crate whose state and contents can be inspected.
+pub trait BufState {
+    /// Writes the state of the buffer, such as its length and sharing, as a comma-separated list.
+    fn write_state<W: Write>(&self, w: &mut W) -> Result<(), io::Error>;
+
+    /// Returns the bytes viewed by the buffer and the offset of the first of them.
+    fn contents(&self) -> (&[u8], usize);
+}
+
+impl BufState for Bytes {
+    fn write_state<W: Write>(&self, w: &mut W) -> Result<(), io::Error> {
+        write!(w, "len={}, unique={}", self.len(), self.is_unique())
+    }
+
+    fn contents(&self) -> (&[u8], usize) {
+        (self, 0)
+    }
+}
+
+impl BufState for BytesMut {
+    fn write_state<W: Write>(&self, w: &mut W) -> Result<(), io::Error> {
+        write!(w, "len={}, capacity={}", self.len(), self.capacity())
+    }
+
+    fn contents(&self) -> (&[u8], usize) {
+        (self, 0)
+    }
+}
+
+impl<T: AsRef<[u8]>> BufState for Cursor<T> {
+    fn write_state<W: Write>(&self, w: &mut W) -> Result<(), io::Error> {
+        write!(
+            w,
+            "position={}, remaining={}",
+            self.position(),
+            self.remaining()
+        )
+    }
+
+    fn contents(&self) -> (&[u8], usize) {
+        let inner = self.get_ref().as_ref();
+        let pos = (self.position() as usize).min(inner.len());
+        (&inner[pos..], pos)
+    }
+}
+
+#[doc(hidden)]
+pub fn write_buf_internal<W: Write, T: BufState>(
+    mut w: W,
+    record: &Record<T>,
+) -> Result<(), io::Error> {
+    write_internal(&mut w, record, &Options::default())?;
+    let (data, offset) = record.reference.contents();
+    write!(w, "-----+ {:p}: [u8] = (", data.as_ptr())?;
+    record.reference.write_state(&mut w)?;
+    writeln!(w, ")")?;
+    write_lines(&mut w, data, offset, None, &Options::default())
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn print_buf_internal<T: BufState>(record: &Record<T>) {
+    write_buf_internal(crate::capture::output(io::stdout()), record).unwrap()
+}
+
+/// Prints a buffer of the [`bytes`](::bytes) crate with its state and the bytes it views to stdout.
+///
+/// The header of the buffer itself is followed by its state (length, sharing, capacity or cursor
+/// position) and its contents. The contents of a [`Cursor`] are numbered from its position.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_buf;
+/// use bytes::Bytes;
+///
+/// let b = Bytes::from(vec![1, 2, 3]);
+/// binspect_buf!(b);
+/// let c = b.clone();
+/// binspect_buf!(c);
+/// ```
+#[macro_export]
+macro_rules! binspect_buf {
+    ($v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::buf::print_buf_internal(&$crate::record!(t, $v, bs, true));
+    }};
+}
+
+/// Writes a buffer of the [`bytes`](::bytes) crate with its state and the bytes it views to
+/// [`std::io::Write`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_buf;
+/// use bytes::Buf;
+/// use std::io::Cursor;
+///
+/// let mut c = Cursor::new(b"\x00\x01\x02\x03".to_vec());
+/// c.advance(2);
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_buf!(&mut buf, c).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains("(position=2, remaining=2)\n0002 | 02 03\n"));
+/// ```
+#[macro_export]
+macro_rules! write_binspect_buf {
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::buf::write_buf_internal($w, &$crate::record!(t, $v, bs, true))
+    }};
+}
@@ -0,0 +1,158 @@
+use std::io::{self, Write};
+
+use crate::limit;
+use crate::schema::Schema;
+
+/// Size of the cache lines, which is 64 bytes on most x86-64 and ARM processors.
+const LINE: usize = 64;
+
+#[doc(hidden)]
+pub struct Entry<'a> {
+    source: &'a str,
+    address: usize,
+    size: usize,
+    schema: Option<Schema>,
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn entry<'a, T: ?Sized>(t: &'a T, source: &'a str, schema: Option<Schema>) -> Entry<'a> {
+    Entry {
+        source,
+        address: t as *const T as *const u8 as usize,
+        size: std::mem::size_of_val(t),
+        schema,
+    }
+}
+
+/// A named range of bytes in memory: an object, or a field of an object with fields.
+struct Part {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+fn parts(entries: &[Entry]) -> Vec<Part> {
+    let mut parts = vec![];
+    for entry in entries {
+        match &entry.schema {
+            Some(schema) if !schema.fields().is_empty() => {
+                for field in schema.fields() {
+                    parts.push(Part {
+                        name: format!("{}.{}", entry.source, field.name),
+                        start: entry.address + field.offset,
+                        end: entry.address + field.end(),
+                    });
+                }
+            }
+            _ => parts.push(Part {
+                name: entry.source.to_owned(),
+                start: entry.address,
+                end: entry.address + entry.size,
+            }),
+        }
+    }
+    parts
+}
+
+#[doc(hidden)]
+pub fn write_cachelines_internal<W: Write>(mut w: W, entries: &[Entry]) -> Result<(), io::Error> {
+    let parts = parts(entries);
+    let mut lines: Vec<usize> = parts
+        .iter()
+        .filter(|p| p.end > p.start)
+        .flat_map(|p| (p.start / LINE..(p.end - 1) / LINE + 1).map(|l| l * LINE))
+        .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    let mut shared = 0;
+    for &line in &lines {
+        let users: Vec<&Part> = parts
+            .iter()
+            .filter(|p| p.start < line + LINE && line < p.end)
+            .collect();
+        if users.len() < 2 {
+            continue;
+        }
+        shared += 1;
+        write!(w, "{:p} |", line as *const u8)?;
+        for (i, part) in users.iter().enumerate() {
+            let start = part.start.max(line) - line;
+            let end = part.end.min(line + LINE) - line;
+            let separator = if i == 0 { "" } else { "," };
+            write!(w, "{} {} {:02x}..{:02x}", separator, part.name, start, end)?;
+        }
+        writeln!(w)?;
+    }
+    writeln!(w, "     + {} of {} cache lines shared", shared, lines.len())
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn print_cachelines_internal(entries: &[Entry]) {
+    limit::limited(io::stdout(), |w| write_cachelines_internal(w, entries)).unwrap()
+}
+
+/// Prints the 64-byte cache lines shared by several objects to stdout, to find false sharing.
+///
+/// Each shared line is printed with its address and the objects in it, with their byte ranges
+/// within the line. Objects of types implementing [`Binspect`](crate::Binspect) are split into
+/// their fields, so that fields written by different threads can be told apart.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_cachelines;
+/// let counters = [0u64; 4];
+/// let (head, tail) = (0usize, 0usize);
+/// binspect_cachelines!(counters[0], counters[1], head, tail);
+/// ```
+#[macro_export]
+macro_rules! binspect_cachelines {
+    ($($v: expr),+ $(,)?) => {
+        $crate::print_cachelines_internal(&[$($crate::cacheline_entry!($v)),+])
+    };
+}
+
+/// Writes the cache lines shared by several objects to a [`Write`](std::io::Write), as
+/// [`binspect_cachelines!`] does.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_cachelines;
+/// #[repr(C, align(64))]
+/// struct Queue {
+///     head: u64,
+///     tail: u64,
+///     padded: [u64; 8],
+/// }
+///
+/// let q = Queue { head: 0, tail: 0, padded: [0; 8] };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_cachelines!(&mut buf, q.head, q.tail, q.padded).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "0 | q.head 00..08, q.tail 08..10, q.padded 10..40\n\
+///      \x20    + 1 of 2 cache lines shared\n"
+/// ));
+/// ```
+#[macro_export]
+macro_rules! write_binspect_cachelines {
+    ($w: expr, $($v: expr),+ $(,)?) => {
+        $crate::write_cachelines_internal($w, &[$($crate::cacheline_entry!($v)),+])
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! cacheline_entry {
+    ($v: expr) => {
+        match &$v {
+            t => $crate::cacheline_entry(t, stringify!($v), {
+                #[allow(unused_imports)]
+                use $crate::{ViaBinspect as _, ViaNone as _};
+                (&$crate::SchemaProbe(t)).binspect_schema()
+            }),
+        }
+    };
+}
@@ -0,0 +1,210 @@
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::mem;
+
+use crate::sink;
+
+thread_local! {
+    static LOCAL: Cell<Option<*mut (dyn Write + 'static)>> = Cell::new(None);
+}
+
+/// Runs `f` and returns what the macros printing to stdout or stderr, such as
+/// [`binspect!`](crate::binspect) and [`ebinspect!`](crate::ebinspect), printed in it on this
+/// thread, instead of printing it.
+///
+/// This makes code calling the printing macros testable without rewriting it to use
+/// [`write_binspect!`](crate::write_binspect). It is a shorthand for [`with_sink`] with a buffer.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{binspect, capture, ebinspect};
+///
+/// let v = 0x0102u16;
+/// let out = capture(|| {
+///     binspect!(v);
+///     ebinspect!(v);
+/// });
+/// assert_eq!(out.matches(": u16 = v\n0000 | 02 01\n").count(), 2);
+/// ```
+pub fn capture<F: FnOnce()>(f: F) -> String {
+    let mut buf: Vec<u8> = vec![];
+    with_sink(&mut buf, f);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Runs `f` with the output of the macros printing to stdout or stderr on this thread sent to
+/// `w`, e.g. so that parallel tests or the handlers of requests get their own dumps.
+///
+/// The thread-local sink takes precedence over the global sink of
+/// [`set_sink`](crate::set_sink). Its output is not counted towards
+/// [`set_max_output`](crate::set_max_output), and the `Auto` color choice never colors it. Dumps
+/// printed by other threads, including threads spawned by `f`, are not redirected. Calls may be
+/// nested; the inner call takes the output of its closure. Stdout and stderr are not locked for
+/// redirected dumps, so they are not held up by another thread holding the lock.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+///
+/// use binspect::{binspect, with_sink};
+///
+/// let handles: Vec<_> = (0..4u8)
+///     .map(|i| {
+///         thread::spawn(move || {
+///             let mut log: Vec<u8> = vec![];
+///             with_sink(&mut log, || binspect!(i));
+///             String::from_utf8(log).unwrap()
+///         })
+///     })
+///     .collect();
+/// for (i, handle) in handles.into_iter().enumerate() {
+///     assert!(handle.join().unwrap().ends_with(&format!(": u8 = i\n0000 | {:02x}\n", i)));
+/// }
+/// ```
+///
+/// ```
+/// use std::io;
+/// use std::sync::mpsc;
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// use binspect::{binspect, capture};
+///
+/// let (locked_tx, locked_rx) = mpsc::channel();
+/// let (done_tx, done_rx) = mpsc::channel::<()>();
+/// let holder = thread::spawn(move || {
+///     let stdout = io::stdout();
+///     let _lock = stdout.lock();
+///     locked_tx.send(()).unwrap();
+///     done_rx.recv_timeout(Duration::from_secs(5)).is_ok()
+/// });
+/// locked_rx.recv().unwrap();
+/// let v = 1u8;
+/// assert!(capture(|| binspect!(v)).ends_with(": u8 = v\n0000 | 01\n"));
+/// let _ = done_tx.send(());
+/// assert!(holder.join().unwrap(), "capture waited for the lock of stdout");
+/// ```
+///
+/// A sink which panics keeps receiving the output of the later dumps in `f`:
+///
+/// ```
+/// use std::io::{self, Write};
+/// use std::panic;
+///
+/// use binspect::{binspect, with_sink};
+///
+/// struct Flaky(Vec<u8>, bool);
+///
+/// impl Write for Flaky {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         if self.1 {
+///             self.1 = false;
+///             panic!("flaky");
+///         }
+///         self.0.write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let mut w = Flaky(vec![], true);
+/// let (a, b) = (1u8, 2u8);
+/// with_sink(&mut w, || {
+///     assert!(panic::catch_unwind(|| binspect!(a)).is_err());
+///     binspect!(b);
+/// });
+/// assert!(String::from_utf8(w.0).unwrap().ends_with(": u8 = b\n0000 | 02\n"));
+/// ```
+pub fn with_sink<R, F: FnOnce() -> R>(w: &mut dyn Write, f: F) -> R {
+    struct Restore(Option<*mut (dyn Write + 'static)>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LOCAL.with(|l| l.set(self.0));
+        }
+    }
+
+    // The lifetime of `w` is erased to store it, but the sink of an outer call is restored before
+    // `w` is borrowed again, even if `f` panics.
+    let w: *mut (dyn Write + '_) = w;
+    let w: *mut (dyn Write + 'static) = unsafe { mem::transmute(w) };
+    let _restore = Restore(LOCAL.with(|l| l.replace(Some(w))));
+    f()
+}
+
+/// Returns whether the output of this thread is redirected by [`with_sink`].
+pub(crate) fn is_redirected() -> bool {
+    LOCAL.with(|l| l.get().is_some())
+}
+
+/// Runs `f` with the sink of [`with_sink`], or returns `None` if there is none.
+fn with_local<T, F: FnOnce(&mut dyn Write) -> T>(f: F) -> Option<T> {
+    LOCAL.with(|l| {
+        // The sink is taken while it is used, so that dumps printed by the sink itself go to the
+        // outer sinks rather than into a second borrow of it.
+        let w = l.take()?;
+        let put = Put(l, w);
+        Some(f(unsafe { &mut *put.1 }))
+    })
+}
+
+/// Puts the sink taken by [`with_local`] back on drop, even if it panics.
+struct Put<'a>(
+    &'a Cell<Option<*mut (dyn Write + 'static)>>,
+    *mut (dyn Write + 'static),
+);
+
+impl Drop for Put<'_> {
+    fn drop(&mut self) {
+        self.0.set(Some(self.1));
+    }
+}
+
+/// A writer to stdout or stderr which writes to the sink of [`with_sink`] or else to the sink of
+/// [`set_sink`](crate::set_sink) instead, if one is set.
+pub(crate) struct Output<W> {
+    inner: W,
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        if let Some(result) = with_local(|w| w.write(buf)) {
+            return result;
+        }
+        match sink::write(buf) {
+            Some(result) => result,
+            None => self.inner.write(buf),
+        }
+    }
+
+    // Stdout and stderr lock themselves for each call, so they are only locked for output which
+    // is not redirected, and a whole dump is written under a single lock.
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), io::Error> {
+        if let Some(result) = with_local(|w| w.write_all(buf)) {
+            return result;
+        }
+        match sink::write(buf) {
+            Some(result) => result.map(|_| ()),
+            None => self.inner.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        if let Some(result) = with_local(|w| w.flush()) {
+            return result;
+        }
+        match sink::flush() {
+            Some(result) => result,
+            None => self.inner.flush(),
+        }
+    }
+}
+
+/// Wraps stdout or stderr to be redirected to the sinks. The handles are passed unlocked.
+pub(crate) fn output<W: Write>(inner: W) -> Output<W> {
+    Output { inner }
+}
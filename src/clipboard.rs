@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Commands reading the clipboard contents from stdin, tried in order.
+#[cfg(target_os = "macos")]
+const COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(windows)]
+const COMMANDS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", windows)))]
+const COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+/// Places `text` on the system clipboard.
+///
+/// This runs the clipboard utility of the platform: `pbcopy` on macOS, `clip` on Windows, and
+/// `wl-copy`, `xclip` or `xsel`, whichever is found first, elsewhere.
+pub fn copy_to_clipboard(text: &str) -> Result<(), io::Error> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no clipboard utility found");
+    for command in COMMANDS {
+        let child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                last_error = e;
+                continue;
+            }
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(());
+        }
+        last_error = io::Error::new(
+            io::ErrorKind::Other,
+            format!("{} exited with {}", command[0], status),
+        );
+    }
+    Err(last_error)
+}
+
+/// Places the memory address and the hex representation of an object on the system clipboard
+/// (requires the `clipboard` feature).
+///
+/// See [`copy_to_clipboard`] for the utilities used.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use binspect::copy_binspect;
+/// let s = "ABC";
+/// copy_binspect!(*s).unwrap();
+/// ```
+#[macro_export]
+macro_rules! copy_binspect {
+    ($v: expr) => {{
+        let mut buf: Vec<u8> = vec![];
+        match $crate::write_binspect!(&mut buf, $v) {
+            Ok(()) => $crate::copy_to_clipboard(&String::from_utf8_lossy(&buf)),
+            Err(e) => Err(e),
+        }
+    }};
+    ($v: expr, $len: expr) => {{
+        let mut buf: Vec<u8> = vec![];
+        match $crate::write_binspect!(&mut buf, $v, $len) {
+            Ok(()) => $crate::copy_to_clipboard(&String::from_utf8_lossy(&buf)),
+            Err(e) => Err(e),
+        }
+    }};
+}
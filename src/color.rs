@@ -0,0 +1,153 @@
+//! ANSI colors of byte classes, similar to `hexyl`, and the detection of terminals.
+
+use std::env;
+use std::fmt;
+
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// Whether the text format is colored.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{binspect, capture};
+///
+/// let v = [0u8, 0x41];
+/// std::env::set_var("BINSPECT_COLOR", "always");
+/// assert!(capture(|| binspect!(v)).contains("\x1b["));
+/// std::env::set_var("BINSPECT_COLOR", "never");
+/// assert!(!capture(|| binspect!(v)).contains("\x1b["));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colors the output of [`binspect!`](crate::binspect) and [`ebinspect!`](crate::ebinspect)
+    /// if stdout or stderr is a terminal and the `NO_COLOR` environment variable is not set.
+    /// Other output is not colored. The `BINSPECT_COLOR` environment variable overrides this with
+    /// `always` or `never`.
+    Auto,
+    Always,
+    Never,
+}
+
+impl Default for ColorChoice {
+    fn default() -> ColorChoice {
+        ColorChoice::Auto
+    }
+}
+
+/// A standard stream the output is printed to.
+#[derive(Clone, Copy)]
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Parses the value of `BINSPECT_COLOR`.
+pub(crate) fn parse_choice(s: &str) -> Option<ColorChoice> {
+    match s {
+        "auto" => Some(ColorChoice::Auto),
+        "always" => Some(ColorChoice::Always),
+        "never" => Some(ColorChoice::Never),
+        _ => None,
+    }
+}
+
+/// Resolves [`ColorChoice::Auto`] for output printed to `stream`.
+pub(crate) fn resolve(choice: ColorChoice, stream: Stream) -> ColorChoice {
+    if choice != ColorChoice::Auto {
+        return choice;
+    }
+    let choice = env::var("BINSPECT_COLOR").ok();
+    match choice.as_deref().and_then(parse_choice) {
+        Some(ColorChoice::Auto) | None => {}
+        Some(choice) => return choice,
+    }
+    let no_color = env::var_os("NO_COLOR").map_or(false, |v| !v.is_empty());
+    let redirected = crate::capture::is_redirected() || crate::sink::is_set();
+    if !no_color && !redirected && is_terminal(stream) {
+        ColorChoice::Always
+    } else {
+        ColorChoice::Never
+    }
+}
+
+#[cfg(unix)]
+fn is_terminal(stream: Stream) -> bool {
+    use std::os::raw::c_int;
+
+    extern "C" {
+        fn isatty(fd: c_int) -> c_int;
+    }
+
+    let fd = match stream {
+        Stream::Stdout => 1,
+        Stream::Stderr => 2,
+    };
+    unsafe { isatty(fd) != 0 }
+}
+
+#[cfg(windows)]
+fn is_terminal(stream: Stream) -> bool {
+    use std::os::raw::{c_ulong, c_void};
+
+    const STD_OUTPUT_HANDLE: c_ulong = -11i32 as c_ulong;
+    const STD_ERROR_HANDLE: c_ulong = -12i32 as c_ulong;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: c_ulong) -> *mut c_void;
+        fn GetConsoleMode(console_handle: *mut c_void, mode: *mut c_ulong) -> i32;
+    }
+
+    let id = match stream {
+        Stream::Stdout => STD_OUTPUT_HANDLE,
+        Stream::Stderr => STD_ERROR_HANDLE,
+    };
+    let mut mode = 0;
+    unsafe { GetConsoleMode(GetStdHandle(id), &mut mode) != 0 }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_terminal(_stream: Stream) -> bool {
+    false
+}
+
+/// How bytes are colored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorTheme {
+    /// A color per class of bytes: null bytes, printable ASCII, ASCII whitespace, other ASCII and
+    /// non-ASCII bytes.
+    Class,
+    /// A gradient from dark for `0x00` to bright for `0xff`, which shows the structure of large
+    /// dumps at a glance. Requires a terminal with 256 colors.
+    Heatmap,
+}
+
+impl Default for ColorTheme {
+    fn default() -> ColorTheme {
+        ColorTheme::Class
+    }
+}
+
+/// The escape sequence starting the color of a byte.
+pub(crate) struct Start(pub(crate) ColorTheme, pub(crate) u8);
+
+impl fmt::Display for Start {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.0, self.1) {
+            (ColorTheme::Class, x) => f.write_str(class(x)),
+            // The 24 grays of the 256-color palette, from 232 (darkest) to 255 (brightest).
+            (ColorTheme::Heatmap, x) => write!(f, "\x1b[38;5;{}m", 232 + u32::from(x) * 23 / 255),
+        }
+    }
+}
+
+fn class(x: u8) -> &'static str {
+    match x {
+        0 => "\x1b[90m",
+        b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r' | b' ' => "\x1b[32m",
+        x if x.is_ascii_graphic() => "\x1b[36m",
+        x if x.is_ascii() => "\x1b[35m",
+        _ => "\x1b[33m",
+    }
+}
@@ -0,0 +1,554 @@
+use std::any::type_name;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::address::Address;
+use crate::{as_bytes, limit, write_lines, Options};
+
+/// Types whose values own or point to other objects, to walk them with [`graph!`](crate::graph).
+///
+/// Implementations call [`Walker::visit`] with each object pointed to, and `pointees` of each
+/// field which may contain pointers.
+///
+/// Arrays implement this trait up to 32 elements and for powers of two up to 4096 elements; other
+/// arrays can be walked as slices, e.g. `self.buf[..].pointees(walker)`.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{Deep, Walker};
+///
+/// struct Node {
+///     value: u32,
+///     next: Option<Box<Node>>,
+/// }
+///
+/// impl Deep for Node {
+///     fn pointees(&self, walker: &mut Walker) {
+///         self.next.pointees(walker);
+///     }
+/// }
+/// ```
+pub trait Deep {
+    /// Visits the objects this value owns or points to.
+    fn pointees(&self, walker: &mut Walker);
+}
+
+macro_rules! impl_leaf {
+    ($($t: ty),*) => {
+        $(
+            impl Deep for $t {
+                fn pointees(&self, _walker: &mut Walker) {}
+            }
+        )*
+    };
+}
+
+impl_leaf!(u8, u16, u32, u64, u128, usize);
+impl_leaf!(i8, i16, i32, i64, i128, isize);
+impl_leaf!(f32, f64, bool, char, (), str);
+
+macro_rules! impl_array {
+    ($($n: expr),*) => {
+        $(
+            impl<T: Deep> Deep for [T; $n] {
+                fn pointees(&self, walker: &mut Walker) {
+                    self[..].pointees(walker);
+                }
+            }
+        )*
+    };
+}
+
+// Without const generics, arrays of up to 32 elements are covered as in the standard library, and
+// larger ones of powers of two, which are the usual sizes of buffers. Other arrays can be walked
+// as slices.
+impl_array!(
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31, 32, 64, 128, 256, 512, 1024, 2048, 4096
+);
+
+macro_rules! impl_tuple {
+    ($(($($t: ident $i: tt),+)),*) => {
+        $(
+            impl<$($t: Deep),+> Deep for ($($t,)+) {
+                fn pointees(&self, walker: &mut Walker) {
+                    $(self.$i.pointees(walker);)+
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5)
+);
+
+impl<T: Deep> Deep for [T] {
+    fn pointees(&self, walker: &mut Walker) {
+        for x in self {
+            x.pointees(walker);
+        }
+    }
+}
+
+impl<T: Deep> Deep for Option<T> {
+    fn pointees(&self, walker: &mut Walker) {
+        if let Some(x) = self {
+            x.pointees(walker);
+        }
+    }
+}
+
+impl<T: Deep + ?Sized> Deep for &T {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(&**self);
+    }
+}
+
+impl<T: Deep + ?Sized> Deep for &mut T {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(&**self);
+    }
+}
+
+impl<T: Deep + ?Sized> Deep for Box<T> {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(&**self);
+    }
+}
+
+impl<T: Deep + ?Sized> Deep for Rc<T> {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(&**self);
+    }
+}
+
+impl<T: Deep + ?Sized> Deep for Arc<T> {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(&**self);
+    }
+}
+
+impl<T: Deep> Deep for Vec<T> {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(&self[..]);
+    }
+}
+
+impl Deep for String {
+    fn pointees(&self, walker: &mut Walker) {
+        walker.visit(self.as_str());
+    }
+}
+
+impl<T: Deep + ?Sized> Deep for RefCell<T> {
+    fn pointees(&self, walker: &mut Walker) {
+        // Values which are mutably borrowed right now are skipped. The walker copies what it
+        // collects, so nothing refers into the cell once the guard is dropped.
+        if let Ok(guard) = self.try_borrow() {
+            guard.pointees(walker);
+        }
+    }
+}
+
+/// The limits of a [`Walker`].
+#[derive(Clone, Copy)]
+enum Limit {
+    Depth,
+    Nodes,
+    Bytes,
+}
+
+/// The objects skipped by a limit and their total size.
+#[derive(Clone, Copy, Default)]
+struct Skipped {
+    objects: usize,
+    bytes: usize,
+}
+
+/// What points to an object: a root, by its index in [`Walker::roots`], or another object.
+#[derive(Clone, Copy)]
+enum Referrer {
+    Root(usize),
+    Node(usize),
+}
+
+struct Node {
+    address: *const u8,
+    type_name: &'static str,
+    bytes: Vec<u8>,
+    referrers: Vec<Referrer>,
+}
+
+/// Collects the objects reachable from a set of roots, each once, for [`graph!`](crate::graph).
+///
+/// Objects are identified by their address and type, so cyclic structures are walked once, and
+/// pointers back to an object being walked are reported as cycles. The bytes of each object and
+/// the names of the roots are copied when they are collected, so the walker borrows nothing and
+/// shows the objects as they were during the walk.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{Deep, Walker};
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// struct Node {
+///     next: RefCell<Option<Rc<Node>>>,
+/// }
+///
+/// impl Deep for Node {
+///     fn pointees(&self, walker: &mut Walker) {
+///         self.next.pointees(walker);
+///     }
+/// }
+///
+/// let a = Rc::new(Node { next: RefCell::new(None) });
+/// let b = Rc::new(Node { next: RefCell::new(Some(a.clone())) });
+/// *a.next.borrow_mut() = Some(b.clone());
+///
+/// let mut walker = Walker::new().max_depth(8);
+/// walker.root(&a, "a");
+/// let mut buf: Vec<u8> = vec![];
+/// walker.write(&mut buf).unwrap();
+/// assert_eq!(walker.len(), 3);
+/// assert!(String::from_utf8(buf).unwrap().ends_with("cycle: #2 -> #1\n"));
+/// # a.next.borrow_mut().take();
+/// ```
+///
+/// Objects changed or freed after the walk are still shown as they were:
+///
+/// ```
+/// use binspect::Walker;
+/// use std::cell::RefCell;
+///
+/// let cell = RefCell::new(vec![1u8, 2]);
+/// let name = RefCell::new(String::from("cell"));
+/// let mut walker = Walker::new();
+/// walker.root(&cell, name.borrow().as_str());
+/// *cell.borrow_mut() = vec![];
+/// *name.borrow_mut() = "Z".repeat(64);
+/// let mut buf: Vec<u8> = vec![];
+/// walker.write(&mut buf).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains("> = cell [#0]\n"));
+/// assert!(out.ends_with(": [u8] = *#0 [#1]\n0000 | 01 02\n"));
+/// ```
+#[derive(Default)]
+pub struct Walker {
+    roots: Vec<String>,
+    nodes: Vec<Node>,
+    index: HashMap<(usize, &'static str), usize>,
+    current: Option<Referrer>,
+    path: Vec<usize>,
+    back_edges: Vec<(usize, usize)>,
+    max_depth: Option<usize>,
+    max_nodes: Option<usize>,
+    max_bytes: Option<usize>,
+    bytes: usize,
+    skipped_depth: Skipped,
+    skipped_nodes: Skipped,
+    skipped_bytes: Skipped,
+    /// The objects skipped so far with the limit which skipped them, so that objects reached
+    /// several times are counted once.
+    skipped: HashMap<(usize, &'static str), Limit>,
+}
+
+impl Walker {
+    /// Creates a walker without objects or limits.
+    pub fn new() -> Walker {
+        Walker::default()
+    }
+
+    /// Sets the maximum number of pointers followed from a root. Objects further away are skipped.
+    pub fn max_depth(mut self, max_depth: usize) -> Walker {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Sets the maximum number of objects collected. Objects found later are skipped.
+    ///
+    /// Skipped objects are counted once, however many pointers to them are found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binspect::Walker;
+    /// use std::rc::Rc;
+    ///
+    /// let shared = Rc::new(7u32);
+    /// let roots = vec![shared.clone(), shared.clone()];
+    /// let mut walker = Walker::new().max_nodes(2);
+    /// walker.root(&roots, "roots");
+    /// let mut buf: Vec<u8> = vec![];
+    /// walker.write(&mut buf).unwrap();
+    /// let out = String::from_utf8(buf).unwrap();
+    /// assert!(out.ends_with("truncated: 1 object (4 bytes) beyond max_nodes 2\n"));
+    /// ```
+    pub fn max_nodes(mut self, max_nodes: usize) -> Walker {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Sets the maximum total size of the objects collected. Objects which would exceed it are
+    /// skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binspect::Walker;
+    ///
+    /// let blocks = vec![Box::new([0u8; 64]); 100];
+    /// let mut walker = Walker::new().max_bytes(1024);
+    /// walker.root(&blocks, "blocks");
+    /// let mut buf: Vec<u8> = vec![];
+    /// walker.write(&mut buf).unwrap();
+    /// let out = String::from_utf8(buf).unwrap();
+    /// assert!(out.lines().count() < 100);
+    /// assert!(out.ends_with(" bytes) beyond max_bytes 1024\n"));
+    /// ```
+    pub fn max_bytes(mut self, max_bytes: usize) -> Walker {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Adds a root named `name`, and the objects reachable from it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called while walking, i.e. from [`Deep::pointees`], which should call
+    /// [`visit`](Walker::visit) instead.
+    ///
+    /// ```should_panic
+    /// use binspect::{Deep, Walker};
+    /// use std::cell::RefCell;
+    ///
+    /// struct Named {
+    ///     name: RefCell<String>,
+    /// }
+    ///
+    /// impl Deep for Named {
+    ///     fn pointees(&self, walker: &mut Walker) {
+    ///         walker.root(&0u8, self.name.borrow().as_str());
+    ///     }
+    /// }
+    ///
+    /// let named = Named { name: RefCell::new("x".repeat(32)) };
+    /// let mut walker = Walker::new();
+    /// walker.root(&named, "named");
+    /// ```
+    pub fn root<T: Deep + ?Sized, S: Into<String>>(&mut self, t: &T, name: S) {
+        assert!(self.current.is_none(), "Walker::root called while walking");
+        self.roots.push(name.into());
+        self.current = Some(Referrer::Root(self.roots.len() - 1));
+        self.add(t);
+        self.current = None;
+    }
+
+    /// Adds an object pointed to by the object being walked, and the objects reachable from it.
+    ///
+    /// Empty objects, such as the elements of an empty `Vec`, are skipped.
+    pub fn visit<T: Deep + ?Sized>(&mut self, t: &T) {
+        if !as_bytes(t).is_empty() {
+            self.add(t);
+        }
+    }
+
+    fn add<T: Deep + ?Sized>(&mut self, t: &T) {
+        let bytes = as_bytes(t);
+        let address = t as *const T as *const u8;
+        let key = (address as usize, type_name::<T>());
+        let referrer = self.current.expect("visit called outside of a walk");
+        if let Some(&i) = self.index.get(&key) {
+            match referrer {
+                Referrer::Node(from) if self.path.contains(&i) => self.back_edges.push((from, i)),
+                _ => self.nodes[i].referrers.push(referrer),
+            }
+            return;
+        }
+        let limit = if self.max_depth.map_or(false, |d| self.path.len() > d) {
+            Some(Limit::Depth)
+        } else if self.max_nodes.map_or(false, |n| self.nodes.len() >= n) {
+            Some(Limit::Nodes)
+        } else if self
+            .max_bytes
+            .map_or(false, |n| self.bytes + bytes.len() > n)
+        {
+            Some(Limit::Bytes)
+        } else {
+            None
+        };
+        // An object skipped before may be collected now, e.g. when it is reached again closer to a
+        // root, and is then no longer counted as skipped.
+        if let Some(previous) = self.skipped.remove(&key) {
+            let skipped = self.skipped_mut(previous);
+            skipped.objects -= 1;
+            skipped.bytes -= bytes.len();
+        }
+        if let Some(limit) = limit {
+            let skipped = self.skipped_mut(limit);
+            skipped.objects += 1;
+            skipped.bytes += bytes.len();
+            self.skipped.insert(key, limit);
+            return;
+        }
+        self.bytes += bytes.len();
+        let i = self.nodes.len();
+        self.index.insert(key, i);
+        self.nodes.push(Node {
+            address,
+            type_name: type_name::<T>(),
+            bytes: bytes.to_vec(),
+            referrers: vec![referrer],
+        });
+        let parent = self.current.take();
+        self.current = Some(Referrer::Node(i));
+        self.path.push(i);
+        t.pointees(self);
+        self.path.pop();
+        self.current = parent;
+    }
+
+    fn skipped_mut(&mut self, limit: Limit) -> &mut Skipped {
+        match limit {
+            Limit::Depth => &mut self.skipped_depth,
+            Limit::Nodes => &mut self.skipped_nodes,
+            Limit::Bytes => &mut self.skipped_bytes,
+        }
+    }
+
+    /// Returns the number of objects collected.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether no objects are collected.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Writes each object, followed by the objects reached more than once and where from, the
+    /// pointers closing cycles, and the objects skipped by each limit.
+    ///
+    /// Skipped objects are not walked, so the objects reachable only through them are not counted.
+    pub fn write<W: Write>(&self, mut w: W) -> Result<(), io::Error> {
+        let options = Options::default();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let address = Address(node.address, &options);
+            write!(w, "-----+ {}: {} = ", address, node.type_name)?;
+            self.write_referrer(&mut w, node.referrers[0], "*")?;
+            writeln!(w, " [#{}]", i)?;
+            write_lines(&mut w, &node.bytes, 0, None, &options)?;
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.referrers.len() > 1 {
+                write!(w, "shared: #{} <-", i)?;
+                for (j, &referrer) in node.referrers.iter().enumerate() {
+                    write!(w, "{}", if j == 0 { " " } else { ", " })?;
+                    self.write_referrer(&mut w, referrer, "")?;
+                }
+                writeln!(w)?;
+            }
+        }
+        for &(from, to) in &self.back_edges {
+            writeln!(w, "cycle: #{} -> #{}", from, to)?;
+        }
+        let limits = [
+            ("max_depth", self.max_depth, self.skipped_depth),
+            ("max_nodes", self.max_nodes, self.skipped_nodes),
+            ("max_bytes", self.max_bytes, self.skipped_bytes),
+        ];
+        for &(name, limit, skipped) in &limits {
+            if skipped.objects > 0 {
+                writeln!(
+                    w,
+                    "truncated: {} object{} ({} bytes) beyond {} {}",
+                    skipped.objects,
+                    if skipped.objects == 1 { "" } else { "s" },
+                    skipped.bytes,
+                    name,
+                    limit.unwrap_or(0)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_referrer<W: Write>(
+        &self,
+        w: &mut W,
+        referrer: Referrer,
+        deref: &str,
+    ) -> Result<(), io::Error> {
+        match referrer {
+            Referrer::Root(i) => write!(w, "{}", self.roots[i]),
+            Referrer::Node(i) => write!(w, "{}#{}", deref, i),
+        }
+    }
+
+    /// Prints the objects to stdout, as [`Walker::write`] does.
+    pub fn print(&self) {
+        limit::limited(io::stdout(), |w| self.write(w)).unwrap()
+    }
+}
+
+/// Prints the objects reachable from the given roots to stdout, each once, followed by the objects
+/// reached more than once.
+///
+/// The roots must implement [`Deep`]. Each object is labeled `[#n]` and refers to the object
+/// pointing to it first, e.g. `*#0`.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::graph;
+/// let a = vec![1u32, 2];
+/// let b = Box::new(a.clone());
+/// graph!(a, b);
+/// ```
+#[macro_export]
+macro_rules! graph {
+    ($($v: expr),+ $(,)?) => {{
+        let mut walker = $crate::Walker::new();
+        $(walker.root(&$v, stringify!($v));)+
+        walker.print();
+    }};
+}
+
+/// Writes the objects reachable from the given roots to a [`Write`](std::io::Write), as
+/// [`graph!`] does.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_graph;
+/// use std::rc::Rc;
+///
+/// let config = Rc::new(7u32);
+/// let a = vec![config.clone()];
+/// let b = (1u8, config.clone());
+/// let mut buf: Vec<u8> = vec![];
+/// write_graph!(&mut buf, a, b).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(": alloc::vec::Vec<alloc::rc::Rc<u32>> = a [#0]\n"));
+/// assert!(out.contains(": u32 = *#1 [#2]\n0000 | 07 00 00 00\n"));
+/// assert!(out.ends_with("shared: #2 <- #1, #3\n"));
+/// ```
+#[macro_export]
+macro_rules! write_graph {
+    ($w: expr, $($v: expr),+ $(,)?) => {{
+        let mut walker = $crate::Walker::new();
+        $(walker.root(&$v, stringify!($v));)+
+        walker.write($w)
+    }};
+}
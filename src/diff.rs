@@ -0,0 +1,268 @@
+use std::any::type_name;
+use std::io::{self, Write};
+
+use crate::{filter, limit, Record, WIDTH};
+
+/// Writes the ranges of bytes which differ between `old` and `new`, split into lines of at most
+/// [`WIDTH`] bytes, as pairs of `-` and `+` lines. Returns the number of differing bytes.
+pub(crate) fn write_diff<W: Write>(w: &mut W, old: &[u8], new: &[u8]) -> Result<usize, io::Error> {
+    let len = old.len().max(new.len());
+    let differs = |i: usize| old.get(i) != new.get(i);
+    let mut count = 0;
+    let mut i = 0;
+    while i < len {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < len && i - start < WIDTH && differs(i) {
+            i += 1;
+        }
+        count += i - start;
+        for (sign, bytes) in [('-', old), ('+', new)].iter() {
+            let range = &bytes[start.min(bytes.len())..i.min(bytes.len())];
+            if range.is_empty() {
+                continue;
+            }
+            write!(w, "{:04x} | {}", start, sign)?;
+            for x in range {
+                write!(w, " {:02x}", x)?;
+            }
+            writeln!(w)?;
+        }
+    }
+    Ok(count)
+}
+
+/// Writes each bit which differs between `old` and `new`, one per line such as
+/// `byte 5 bit 3: 0 -> 1`, where bit 0 is the least significant bit. Returns the number of
+/// differing bits.
+///
+/// Bytes beyond the shorter of `old` and `new` are not compared.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf: Vec<u8> = vec![];
+/// let flipped = binspect::write_bit_diff(&mut buf, &[0x00, 0x81], &[0x00, 0x89]).unwrap();
+/// assert_eq!(flipped, 1);
+/// assert_eq!(String::from_utf8(buf).unwrap(), "byte 1 bit 3: 0 -> 1\n");
+/// ```
+pub fn write_bit_diff<W: Write>(mut w: W, old: &[u8], new: &[u8]) -> Result<usize, io::Error> {
+    let mut count = 0;
+    for (i, (a, b)) in old.iter().zip(new).enumerate() {
+        let flipped = a ^ b;
+        for bit in 0..8 {
+            if flipped & 1 << bit != 0 {
+                writeln!(
+                    w,
+                    "byte {} bit {}: {} -> {}",
+                    i,
+                    bit,
+                    a >> bit & 1,
+                    b >> bit & 1
+                )?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+#[doc(hidden)]
+pub fn write_diff_internal<W: Write, T: ?Sized>(
+    mut w: W,
+    old: &Record<T>,
+    new: &Record<T>,
+) -> Result<(), io::Error> {
+    writeln!(
+        w,
+        "-----+ {:p}: {} = {} -> {:p} = {}",
+        old.reference,
+        type_name::<T>(),
+        old.source,
+        new.reference,
+        new.source
+    )?;
+    let (old, new) = (old.bytes, new.bytes);
+    let len = old.len().max(new.len());
+    let differs = |i: usize| old.get(i) != new.get(i);
+    let mut count = 0;
+    for start in (0..len).step_by(WIDTH) {
+        let end = (start + WIDTH).min(len);
+        if !(start..end).any(differs) {
+            write_row(&mut w, Some(start), ' ', &old[start..end], |_| false)?;
+            continue;
+        }
+        count += (start..end).filter(|&i| differs(i)).count();
+        let old = &old[start.min(old.len())..end.min(old.len())];
+        let new = &new[start.min(new.len())..end.min(new.len())];
+        write_row(&mut w, Some(start), '-', old, |_| false)?;
+        write_row(&mut w, None, '+', new, |_| false)?;
+        write_row(&mut w, None, ' ', &[0; WIDTH][..end - start], |j| {
+            differs(start + j)
+        })?;
+    }
+    writeln!(w, "     + {} of {} bytes differ", count, len)
+}
+
+/// Writes a line of a side-by-side diff with a sign, or markers under the bytes where `marked`.
+fn write_row<W: Write, F: Fn(usize) -> bool>(
+    w: &mut W,
+    offset: Option<usize>,
+    sign: char,
+    bytes: &[u8],
+    marked: F,
+) -> Result<(), io::Error> {
+    let mut line = match offset {
+        Some(offset) => format!("{:04x} | {}", offset, sign),
+        None => format!("     | {}", sign),
+    };
+    let markers = (0..bytes.len()).any(&marked);
+    for (j, x) in bytes.iter().enumerate() {
+        if j == WIDTH / 2 {
+            line.push_str(if markers { "  " } else { " :" });
+        }
+        if !markers {
+            line.push_str(&format!(" {:02x}", x));
+        } else if marked(j) {
+            line.push_str(" ^^");
+        } else {
+            line.push_str("   ");
+        }
+    }
+    writeln!(w, "{}", line.trim_end())
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn print_diff_internal<T: ?Sized>(old: &Record<T>, new: &Record<T>) {
+    if !filter::is_shown(type_name::<T>()) {
+        return;
+    }
+    limit::limited(io::stdout(), |w| write_diff_internal(w, old, new)).unwrap()
+}
+
+/// Prints two objects of the same type to stdout line by line, marking the bytes which differ.
+///
+/// Lines without differences are printed once. Other lines are printed as a `-` line of the
+/// first object and a `+` line of the second object, followed by `^^` under the differing bytes.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_diff;
+/// let before = (1u32, 2u32);
+/// let after = (1u32, 3u32);
+/// binspect_diff!(before, after);
+/// ```
+///
+/// An example of output:
+///
+/// ```text
+/// -----+ 0x7ffd4c1e66b0: (u32, u32) = before -> 0x7ffd4c1e66b8 = after
+/// 0000 | - 01 00 00 00 02 00 00 00
+///      | + 01 00 00 00 03 00 00 00
+///      |               ^^
+///      + 1 of 8 bytes differ
+/// ```
+#[macro_export]
+macro_rules! binspect_diff {
+    ($old: expr, $new: expr $(,)?) => {{
+        let (old, new) = (&$old, &$new);
+        $crate::print_diff_internal(
+            &$crate::record!(old, $old, $crate::as_bytes(old), true),
+            &$crate::record!(new, $new, $crate::as_bytes(new), true),
+        );
+    }};
+}
+
+/// Writes two objects of the same type to a [`Write`](std::io::Write) line by line, marking the
+/// bytes which differ, as [`binspect_diff!`] does.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_diff;
+/// let mut before = [0u8; 20];
+/// let mut after = before;
+/// after[3] = 0xff;
+/// after[9] = 1;
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_diff!(&mut buf, before, after).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     " = after\n\
+///      0000 | - 00 00 00 00 00 00 00 00 : 00 00 00 00 00 00 00 00\n\
+///      \x20    | + 00 00 00 ff 00 00 00 00 : 00 01 00 00 00 00 00 00\n\
+///      \x20    |            ^^                  ^^\n\
+///      0010 |   00 00 00 00\n\
+///      \x20    + 2 of 20 bytes differ\n"
+/// ));
+/// ```
+#[macro_export]
+macro_rules! write_binspect_diff {
+    ($w: expr, $old: expr, $new: expr $(,)?) => {{
+        let (old, new) = (&$old, &$new);
+        $crate::write_diff_internal(
+            $w,
+            &$crate::record!(old, $old, $crate::as_bytes(old), true),
+            &$crate::record!(new, $new, $crate::as_bytes(new), true),
+        )
+    }};
+}
+
+/// Returns the message of a failed [`binspect_assert_eq!`], or `None` if the bytes are identical.
+/// The macro panics itself so that the panic points at the caller.
+#[doc(hidden)]
+pub fn assert_eq_internal<T: ?Sized>(left: &Record<T>, right: &Record<T>) -> Option<String> {
+    if left.bytes == right.bytes {
+        return None;
+    }
+    let mut buf: Vec<u8> = vec![];
+    write_diff_internal(&mut buf, left, right).unwrap();
+    Some(format!(
+        "assertion failed: bytes of `{}` and `{}` differ\n{}",
+        left.source,
+        right.source,
+        String::from_utf8_lossy(&buf)
+    ))
+}
+
+/// Asserts that two objects of the same type have identical bytes, e.g. after a round trip
+/// through FFI.
+///
+/// On failure, panics with the bytes of both objects as [`binspect_diff!`] prints them.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_assert_eq;
+/// let sent = [1u32, 2];
+/// let received = [1u32, 2];
+/// binspect_assert_eq!(sent, received);
+/// ```
+///
+/// ```should_panic
+/// # use binspect::binspect_assert_eq;
+/// // panics with:
+/// // assertion failed: bytes of `sent` and `received` differ
+/// // -----+ 0x7ffd4c1e66b0: [u32; 2] = sent -> 0x7ffd4c1e66b8 = received
+/// // 0000 | - 01 00 00 00 02 00 00 00
+/// // ...
+/// let sent = [1u32, 2];
+/// let received = [1u32, 3];
+/// binspect_assert_eq!(sent, received);
+/// ```
+#[macro_export]
+macro_rules! binspect_assert_eq {
+    ($left: expr, $right: expr $(,)?) => {{
+        let (left, right) = (&$left, &$right);
+        if let Some(message) = $crate::assert_eq_internal(
+            &$crate::record!(left, $left, $crate::as_bytes(left), true),
+            &$crate::record!(right, $right, $crate::as_bytes(right), true),
+        ) {
+            panic!("{}", message);
+        }
+    }};
+}
@@ -0,0 +1,282 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str;
+
+use crate::{as_bytes, write_internal, Options, Record, Renderer};
+
+/// A dump of an object which can be formatted with `{}`, created by [`binspect_args!`].
+pub struct Dump<'a, T: ?Sized> {
+    record: Record<'a, T>,
+    options: Options,
+    newline: bool,
+}
+
+impl<'a, T: ?Sized> Dump<'a, T> {
+    #[doc(hidden)]
+    pub fn new(record: Record<'a, T>) -> Dump<'a, T> {
+        Dump {
+            record,
+            options: Options::default(),
+            newline: true,
+        }
+    }
+
+    /// Renders the dump with the given options.
+    pub fn options(mut self, options: Options) -> Dump<'a, T> {
+        self.options = options;
+        self
+    }
+
+    /// Leaves out the newline at the end of the dump, for loggers which add their own.
+    pub fn without_newline(mut self) -> Dump<'a, T> {
+        self.newline = false;
+        self
+    }
+}
+
+/// Passes the output of the renderer to a `fmt::Write`, e.g. a formatter, without buffering.
+///
+/// Unless `newline` is set, a trailing newline is held back until more output follows, so that
+/// the newline at the end of the output is left out.
+struct FmtWriter<'a, W: fmt::Write + ?Sized> {
+    w: &'a mut W,
+    newline: bool,
+    pending: bool,
+}
+
+impl<'a, W: fmt::Write + ?Sized> Write for FmtWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        // The renderer writes whole strings, so `buf` never ends in the middle of a character.
+        let s = str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let error = |_| io::Error::new(io::ErrorKind::Other, fmt::Error);
+        if s.is_empty() {
+            return Ok(0);
+        }
+        if self.pending {
+            self.w.write_str("\n").map_err(error)?;
+            self.pending = false;
+        }
+        let s = if !self.newline && s.ends_with('\n') {
+            self.pending = true;
+            &s[..s.len() - 1]
+        } else {
+            s
+        };
+        self.w.write_str(s).map_err(error)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, T: ?Sized> fmt::Display for Dump<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let w = FmtWriter {
+            w: f,
+            newline: self.newline,
+            pending: false,
+        };
+        write_internal(w, &self.record, &self.options).map_err(|_| fmt::Error)
+    }
+}
+
+/// Formats the dump of the referenced object with `{}` or `{:?}`, rendered when formatted.
+///
+/// Unlike [`binspect_args!`](crate::binspect_args), the expression is not known, so the header names the source as
+/// `Hex(..)`, and the dump does not end with a newline, so that it can be embedded in format
+/// strings, e.g. of error messages.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::Hex;
+///
+/// let state = [1u8, 2, 3];
+/// let message = format!("unexpected state: {}", Hex(&state));
+/// assert!(message.starts_with("unexpected state: -----+ 0x"));
+/// assert!(message.ends_with(": [u8; 3] = Hex(..)\n0000 | 01 02 03"));
+/// assert_eq!(format!("{:?}", Hex(&state)), Hex(&state).to_string());
+/// ```
+pub struct Hex<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: ?Sized> fmt::Display for Hex<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let record = Record {
+            reference: self.0,
+            bytes: as_bytes(self.0),
+            sized: true,
+            source: "Hex(..)",
+            label: None,
+            file: "",
+            line: 0,
+            column: 0,
+            schema: None,
+            meta: &[],
+        };
+        let w = FmtWriter {
+            w: f,
+            newline: false,
+            pending: false,
+        };
+        write_internal(w, &record, &Options::default()).map_err(|_| fmt::Error)
+    }
+}
+
+impl<'a, T: ?Sized> fmt::Debug for Hex<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[doc(hidden)]
+pub fn write_fmt_with_internal<W: fmt::Write + ?Sized, T: ?Sized, R: Renderer + ?Sized>(
+    w: &mut W,
+    record: &Record<T>,
+    renderer: &R,
+) -> fmt::Result {
+    let w = FmtWriter {
+        w,
+        newline: true,
+        pending: false,
+    };
+    write_internal(w, record, renderer).map_err(|_| fmt::Error)
+}
+
+/// Returns a dump of an object which can be passed to `format_args!`-like macros, e.g. of loggers
+/// or `panic!`, without an intermediate `String`.
+///
+/// The dump ends with a newline.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_args;
+/// let s = "ABC";
+/// let out = format!("unexpected value:\n{}", binspect_args!(*s));
+/// assert!(out.ends_with(": str = *s\n0000 | 41 42 43\n"));
+/// ```
+///
+/// Other [`Options`](crate::Options) can be given to the returned value:
+///
+/// ```
+/// # use binspect::{binspect_args, Header, Options};
+/// let options = Options {
+///     header: Header::KeyValue,
+///     ..Options::default()
+/// };
+/// let v = 1u16;
+/// let out = format!("{}", binspect_args!(v).options(options));
+/// assert!(out.starts_with("addr=0x"));
+/// ```
+#[macro_export]
+macro_rules! binspect_args {
+    ($v: expr) => {
+        match &$v {
+            t => $crate::Dump::new($crate::record!(t, $v, $crate::as_bytes(t), true)),
+        }
+    };
+    ($v: expr, $len: expr) => {
+        match &$v {
+            t => $crate::Dump::new($crate::record!(
+                t,
+                $v,
+                $crate::as_bytes_with_len(t, $len),
+                false
+            )),
+        }
+    };
+}
+
+/// Writes the memory address and the hex representation of an object to [`std::fmt::Write`], e.g.
+/// a `String` or a `fmt::Formatter`, like [`write_binspect!`](crate::write_binspect) does to
+/// [`std::io::Write`].
+///
+/// The output is passed on as it is rendered, without an intermediate buffer or UTF-8 validation.
+/// Without the `std` feature, only the header and the lines are written, as [`write_hex`](crate::write_hex)
+/// does, with or without a length; [`write_binspect_fmt_with!`] requires `std` to take options.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_fmt;
+/// let s = "ABC";
+/// let mut out = String::new();
+/// write_binspect_fmt!(&mut out, *s).unwrap();
+/// assert!(out.ends_with(": str = *s\n0000 | 41 42 43\n"));
+/// ```
+///
+/// In a `Display` implementation:
+///
+/// ```
+/// # use binspect::write_binspect_fmt;
+/// use std::fmt;
+///
+/// struct Packet([u8; 4]);
+///
+/// impl fmt::Display for Packet {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         writeln!(f, "packet:")?;
+///         write_binspect_fmt!(f, self.0)
+///     }
+/// }
+///
+/// let out = Packet([1, 2, 3, 4]).to_string();
+/// assert!(out.starts_with("packet:\n-----+ 0x"));
+/// assert!(out.ends_with(": [u8; 4] = self.0\n0000 | 01 02 03 04\n"));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_fmt {
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_fmt_with_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::Options::default(),
+        )
+    }};
+    ($w: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_fmt_with_internal(
+            $w,
+            &$crate::record!(t, $v, bs, false),
+            &$crate::Options::default(),
+        )
+    }};
+}
+
+/// Writes an object to [`std::fmt::Write`] like [`write_binspect_fmt!`] with the given
+/// [`Options`](crate::Options), or any other [`Renderer`](crate::Renderer).
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_fmt_with, Format, Options};
+/// let options = Options {
+///     format: Format::Json,
+///     placeholders: true,
+///     ..Options::default()
+/// };
+/// let v = 1u16;
+/// let mut out = String::new();
+/// write_binspect_fmt_with!(&mut out, &options, v).unwrap();
+/// assert!(out.starts_with("{\"addr\":\"[addr]\",\"type\":\"u16\""));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_fmt_with {
+    ($w: expr, $o: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_fmt_with_internal($w, &$crate::record!(t, $v, bs, true), $o)
+    }};
+    ($w: expr, $o: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_fmt_with_internal($w, &$crate::record!(t, $v, bs, false), $o)
+    }};
+}
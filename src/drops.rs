@@ -0,0 +1,154 @@
+use std::any::type_name;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, Write};
+use std::mem::{self, ManuallyDrop};
+use std::ops::{Deref, DerefMut};
+use std::time::Instant;
+
+use crate::{as_bytes, DumpInfo, Options, Renderer};
+
+/// Logs when values wrapped by it are dropped, with a dump of each value just before its drop,
+/// to answer in which order and state values were destroyed.
+///
+/// Values are wrapped with [`binspect_traced!`] or [`trace`](DropTracer::trace). Each drop is
+/// logged as `drop N after T:` with the time since the tracer was created, followed by the dump.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{binspect_traced, DropTracer};
+/// let tracer = DropTracer::with_writer(vec![]);
+/// {
+///     let a = binspect_traced!(tracer, 1u8);
+///     let mut b = binspect_traced!(tracer, [2u8; 2]);
+///     b[1] = 3;
+///     assert_eq!(*a, 1);
+/// }
+/// let out = String::from_utf8(tracer.into_writer()).unwrap();
+/// let b = out.find("drop 1 after ").unwrap();
+/// let a = out.find("drop 2 after ").unwrap();
+/// assert!(b < a);
+/// assert!(out[b..a].ends_with(": [u8; 2] = [2u8; 2]\n0000 | 02 03\n"));
+/// assert!(out[a..].ends_with(": u8 = 1u8\n0000 | 01\n"));
+/// ```
+pub struct DropTracer<W: Write = io::Stderr> {
+    start: Instant,
+    count: Cell<usize>,
+    w: RefCell<W>,
+}
+
+impl DropTracer {
+    /// Creates a tracer logging to stderr.
+    pub fn new() -> DropTracer {
+        DropTracer::with_writer(io::stderr())
+    }
+}
+
+impl Default for DropTracer {
+    fn default() -> DropTracer {
+        DropTracer::new()
+    }
+}
+
+impl<W: Write> DropTracer<W> {
+    /// Creates a tracer logging to `w`.
+    pub fn with_writer(w: W) -> DropTracer<W> {
+        DropTracer {
+            start: Instant::now(),
+            count: Cell::new(0),
+            w: RefCell::new(w),
+        }
+    }
+
+    /// Returns the writer of the log.
+    pub fn into_writer(self) -> W {
+        self.w.into_inner()
+    }
+
+    /// Wraps `value` so that its drop is logged. [`binspect_traced!`] shows the expression too.
+    pub fn trace<T>(&self, value: T) -> Traced<'_, T, W> {
+        self.trace_internal(value, "trace(..)")
+    }
+
+    #[doc(hidden)]
+    pub fn trace_internal<'a, T>(&'a self, value: T, source: &'a str) -> Traced<'a, T, W> {
+        Traced {
+            value: ManuallyDrop::new(value),
+            tracer: self,
+            source,
+        }
+    }
+
+    fn log<T>(&self, value: &T, source: &str) -> Result<(), io::Error> {
+        let count = self.count.get() + 1;
+        self.count.set(count);
+        let mut w = self.w.borrow_mut();
+        writeln!(w, "drop {} after {:?}:", count, self.start.elapsed())?;
+        let info = DumpInfo {
+            address: value as *const T as *const u8,
+            type_name: type_name::<T>(),
+            size: mem::size_of::<T>(),
+            source,
+            label: None,
+            file: "",
+            line: 0,
+            column: 0,
+            schema: None,
+            meta: &[],
+        };
+        Options::default().render(&mut *w, &info, as_bytes(value))
+    }
+}
+
+impl<W: Write> fmt::Debug for DropTracer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DropTracer")
+            .field("start", &self.start)
+            .field("count", &self.count.get())
+            .finish()
+    }
+}
+
+/// A value whose drop is logged by a [`DropTracer`]. It dereferences to the value.
+pub struct Traced<'a, T, W: Write = io::Stderr> {
+    value: ManuallyDrop<T>,
+    tracer: &'a DropTracer<W>,
+    source: &'a str,
+}
+
+impl<'a, T, W: Write> Deref for Traced<'a, T, W> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T, W: Write> DerefMut for Traced<'a, T, W> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<'a, T, W: Write> Drop for Traced<'a, T, W> {
+    fn drop(&mut self) {
+        // Errors of the log must not turn a drop into a panic.
+        let _ = self.tracer.log(&*self.value, self.source);
+        unsafe { ManuallyDrop::drop(&mut self.value) }
+    }
+}
+
+impl<'a, T: fmt::Debug, W: Write> fmt::Debug for Traced<'a, T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.value, f)
+    }
+}
+
+/// Wraps a value with [`DropTracer::trace`], showing the expression in the log of its drop.
+#[macro_export]
+macro_rules! binspect_traced {
+    ($tracer: expr, $v: expr) => {
+        $tracer.trace_internal($v, stringify!($v))
+    };
+}
@@ -0,0 +1,1617 @@
+//! Rendering of dumps with [`Options`] to `io::Write`, which needs `std`.
+
+use std::any::type_name;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::address::Address;
+#[cfg(feature = "color")]
+use crate::color;
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::{
+    bitmap, braille, filter, json, limit, region, schema, varint, yaml, AddressFormat, DumpInfo,
+    Lane, Renderer, Spill, TagScheme, Varints, WIDTH,
+};
+#[cfg(feature = "color")]
+use crate::{ColorChoice, ColorTheme};
+
+#[doc(hidden)]
+pub struct Record<'a, T: ?Sized> {
+    pub reference: &'a T,
+    pub bytes: &'a [u8],
+    pub sized: bool,
+    pub source: &'a str,
+    pub label: Option<&'a str>,
+    pub file: &'a str,
+    pub line: u32,
+    pub column: u32,
+    pub schema: Option<schema::Schema>,
+    pub meta: &'a [(&'a str, &'a dyn fmt::Display)],
+}
+
+impl<'a, T: ?Sized> Record<'a, T> {
+    /// Returns the information on the object passed to renderers.
+    fn info(&self) -> DumpInfo<'_> {
+        DumpInfo {
+            address: self.reference as *const T as *const u8,
+            type_name: type_name::<T>(),
+            size: self.bytes.len(),
+            source: self.source,
+            label: self.label,
+            file: self.file,
+            line: self.line,
+            column: self.column,
+            schema: self.schema.as_ref(),
+            meta: self.meta,
+        }
+    }
+}
+
+/// Output format of a dump.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Format, Options};
+/// let options = Options {
+///     format: Format::Yaml,
+///     placeholders: true,
+///     ..Options::default()
+/// };
+/// let s = "ABC";
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, s).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.starts_with("---\naddr: \"[addr]\"\ntype: \"&str\"\nsize: 16\nsrc: \"s\"\nfile: "));
+/// assert!(out.ends_with("\nbytes: \"################0300000000000000\"\n"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The hex dump with a header line.
+    Text,
+    /// A YAML document per dump, with the header fields as keys and the bytes as a hex string.
+    Yaml,
+    /// A bitmap with a cell per bit, `#` if set and `.` otherwise, for bitsets such as allocation
+    /// bitmaps and Bloom filters. Lines hold the given number of cells, or 64 if `None`, and start
+    /// with the index of their first bit. The least significant bit of each byte comes first.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Format, Options};
+    /// let options = Options {
+    ///     format: Format::Bitmap(Some(12)),
+    ///     ..Options::default()
+    /// };
+    /// let used: [u8; 3] = [0b1000_0011, 0xff, 0x00];
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, used).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with(
+    ///     ": [u8; 3] = used\n\
+    ///      0000 | ##.....# ####\n\
+    ///      000c | #### ........\n\
+    ///      \x20    + 11 of 24 bits set\n"
+    /// ));
+    /// ```
+    Bitmap(Option<usize>),
+    /// A compact overview with a braille glyph per 8 bytes and 512 bytes per line, each dot set
+    /// for a byte above the given threshold, so that large regions fit on a screen. The dots of a
+    /// glyph are the bytes down its left column, then down its right column.
+    ///
+    /// Regions worth a closer look can then be dumped in the text format, e.g.
+    /// `binspect!(buf[0x200..0x240])`, or under the overview with [`Options::zoom`].
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Format, Options};
+    /// let options = Options {
+    ///     format: Format::Braille(0),
+    ///     ..Options::default()
+    /// };
+    /// let mut heap = [0u8; 24];
+    /// heap[..8].copy_from_slice(&[1, 1, 1, 1, 1, 1, 1, 1]);
+    /// heap[8] = 0xff;
+    /// heap[20] = 0x10;
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, heap).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with(
+    ///     ": [u8; 24] = heap\n\
+    ///      0000 | \u{28ff}\u{2801}\u{2808}\n\
+    ///      \x20    + 10 of 24 bytes above 0x00\n"
+    /// ));
+    /// ```
+    Braille(u8),
+    /// A JSON object per dump on a single line, with the header fields as keys and the bytes as
+    /// an array of numbers, `null` for bytes which are not shown. See [`binspect_json!`](crate::binspect_json).
+    Json,
+}
+
+impl Default for Format {
+    fn default() -> Format {
+        Format::Text
+    }
+}
+
+/// Format of the header line of a dump.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Header {
+    /// `-----+ 0x7ffce3c8f7a0: &str = s`
+    Classic,
+    /// `s: &str @ 0x7ffce3c8f7a0`, the source first, for dumps of many small objects.
+    Compact,
+    /// `-----+ 0x7ffce3c8f7a0: &str = s (16 bytes) at src/main.rs:4:5`, the classic header with
+    /// the size and the location of the macro call.
+    Verbose,
+    /// `addr=0x7ffce3c8f7a0 type=&str size=16 src="s" file=src/main.rs:4:5`, a single line of
+    /// `key=value` pairs for log processors. Values containing spaces, `=`, `"`, `\` or control
+    /// characters are quoted, with `"`, `\` and control characters escaped as in Rust strings.
+    /// Characters of meta keys other than alphanumerics, `_`, `-` and `.` are replaced by `_`.
+    KeyValue,
+}
+
+impl Default for Header {
+    fn default() -> Header {
+        Header::Classic
+    }
+}
+
+/// A preset of the options which shape the layout of dumps, to start [`Options`] from.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options, Style};
+/// let options = Options {
+///     ascii: false,
+///     ..Style::Verbose.into()
+/// };
+/// let v = 1u8;
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, v).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(&format!(" = v (1 byte) at {}:", file!())));
+///
+/// let options: Options = Style::Compact.into();
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, v).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.starts_with("v: u8 @ 0x"));
+/// assert!(out.ends_with("\n0000 | 01\n"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// [`Options::default`], with [`Header::Classic`].
+    Classic,
+    /// [`Header::Compact`] without [`regions`](Options::regions).
+    Compact,
+    /// [`Header::Verbose`] with [`ascii`](Options::ascii), [`legend`](Options::legend) and
+    /// [`align_headers`](Options::align_headers).
+    Verbose,
+    /// [`Format::Json`] with [`timestamps`](Options::timestamps), for log processors.
+    Machine,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style::Classic
+    }
+}
+
+impl From<Style> for Options {
+    fn from(style: Style) -> Options {
+        let options = Options::default();
+        match style {
+            Style::Classic => options,
+            Style::Compact => Options {
+                header: Header::Compact,
+                regions: false,
+                ..options
+            },
+            Style::Verbose => Options {
+                header: Header::Verbose,
+                ascii: true,
+                legend: true,
+                align_headers: Some(HeaderColumn::new()),
+                ..options
+            },
+            Style::Machine => Options {
+                format: Format::Json,
+                timestamps: true,
+                ..options
+            },
+        }
+    }
+}
+
+/// A callback returning a comment for a line of a dump, given the offset and the bytes of the line.
+pub type Comment = Arc<dyn Fn(usize, &[u8]) -> Option<String> + Send + Sync>;
+
+/// A key combined with each byte before it is shown, repeated over the bytes of a dump.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Mask, Options};
+/// let options = Options {
+///     mask: Some(Mask::Xor(vec![0x5a])),
+///     ..Options::default()
+/// };
+/// let obfuscated = [0x12_u8, 0x1f, 0x16, 0x16, 0x15];
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, obfuscated).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with("\n0000 | 48 45 4c 4c 4f\n"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mask {
+    /// Shows each byte XORed with the key, e.g. to undo a simple obfuscation.
+    Xor(Vec<u8>),
+    /// Shows each byte ANDed with the key, e.g. to clear tag bits.
+    And(Vec<u8>),
+}
+
+impl Mask {
+    /// Returns the byte shown for the byte `x` at offset `i`.
+    pub fn apply(&self, i: usize, x: u8) -> u8 {
+        match self {
+            Mask::Xor(key) if !key.is_empty() => x ^ key[i % key.len()],
+            Mask::And(key) if !key.is_empty() => x & key[i % key.len()],
+            _ => x,
+        }
+    }
+}
+
+/// Options to render a dump, used by [`binspect_with!`](crate::binspect_with), [`ebinspect_with!`](crate::ebinspect_with) and
+/// [`write_binspect_with!`](crate::write_binspect_with).
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options};
+/// use std::sync::Arc;
+///
+/// let options = Options {
+///     comment: Some(Arc::new(|offset, _row: &[u8]| match offset {
+///         0 => Some("frame header".to_owned()),
+///         _ => None,
+///     })),
+///     ..Options::default()
+/// };
+/// let frame = [0x81_u8, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, frame).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.ends_with("\n0000 | 81 05 48 65 6c 6c 6f                               frame header\n"));
+/// ```
+///
+/// Collapsed dumps keep test output short:
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options};
+/// let options = Options {
+///     collapsed: true,
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, [0xff_u8; 40]).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.ends_with("\n0000 | ff ff ff ff ff ff ff ff : ff ff ff ff ff ff ff ff\n     + 24 more bytes, set BINSPECT_FULL=1 to show all\n"));
+/// ```
+///
+/// An ASCII column shows text at a glance:
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options};
+/// let options = Options {
+///     ascii: true,
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, *b"GET /index.html HTTP/1.1\r\n").unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | 47 45 54 20 2f 69 6e 64 : 65 78 2e 68 74 6d 6c 20  GET /index.html \n\
+///      0010 | 48 54 54 50 2f 31 2e 31 : 0d 0a                    HTTP/1.1..\n"
+/// ));
+/// ```
+///
+/// Word-sized reads render the same bytes with fewer, wider accesses:
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options};
+/// let options = Options {
+///     word_reads: true,
+///     ..Options::default()
+/// };
+/// let data: Vec<u8> = (0..40).collect();
+/// let mut words: Vec<u8> = vec![];
+/// let mut bytes: Vec<u8> = vec![];
+/// write_binspect_with!(&mut words, &options, data[3..]).unwrap();
+/// write_binspect_with!(&mut bytes, &Options::default(), data[3..]).unwrap();
+/// assert_eq!(words, bytes);
+/// ```
+#[derive(Clone)]
+pub struct Options {
+    /// Prints the address of each line instead of its offset.
+    pub absolute: bool,
+    /// Starts lines at addresses which are multiples of 16 with [`absolute`](Options::absolute),
+    /// leaving the cells before the first byte empty, so that the columns of dumps of nearby
+    /// objects line up.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Options};
+    /// let options = Options {
+    ///     absolute: true,
+    ///     align_rows: true,
+    ///     ..Options::default()
+    /// };
+    /// let buf = [0x11u8; 32];
+    /// let mut out: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut out, &options, buf[3..6]).unwrap();
+    /// let line = format!("{:#x} |", &buf[3] as *const u8 as usize / 16 * 16);
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert!(out.contains(&line));
+    /// assert!(out.ends_with("11 11 11\n"));
+    /// ```
+    pub align_rows: bool,
+    /// Replaces addresses and pointer-like bytes with placeholders, as [`binspect_doc!`](crate::binspect_doc) does.
+    pub placeholders: bool,
+    /// Format of the header line.
+    pub header: Header,
+    /// Appends the location of the macro call to the header line, e.g. `at src/main.rs:42:5`.
+    /// The key-value header always has it.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Options};
+    /// let options = Options {
+    ///     location: true,
+    ///     ..Options::default()
+    /// };
+    /// let v = 1u8;
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, v).unwrap();
+    /// let header = String::from_utf8(buf).unwrap().lines().next().unwrap().to_owned();
+    /// assert!(header.contains(&format!(": u8 = v at {}:", file!())));
+    /// ```
+    pub location: bool,
+    /// Pads the classic and verbose headers so that their `=` signs line up at the given
+    /// [`HeaderColumn`], i.e. with those of the previous dumps with these options or their
+    /// clones, up to a column of 60. Escape sequences, e.g. colors, take no width.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, HeaderColumn, Options};
+    /// let options = Options {
+    ///     align_headers: Some(HeaderColumn::new()),
+    ///     placeholders: true,
+    ///     ..Options::default()
+    /// };
+    /// let (flags, name) = (1u8, [0u32; 2]);
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, name).unwrap();
+    /// write_binspect_with!(&mut buf, &options.clone(), flags).unwrap();
+    /// let out = String::from_utf8(buf).unwrap();
+    /// assert!(out.contains("-----+ [addr]: [u32; 2] = name\n"));
+    /// assert!(out.contains("-----+ [addr]: u8       = flags\n"));
+    ///
+    /// let other = Options {
+    ///     align_headers: Some(HeaderColumn::new()),
+    ///     placeholders: true,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &other, flags).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().starts_with("-----+ [addr]: u8 = flags\n"));
+    /// ```
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, AddressFormat, HeaderColumn, Options};
+    /// # use std::sync::Arc;
+    /// let options = Options {
+    ///     address: AddressFormat::Custom(Arc::new(|_, f| f.write_str("\x1b[1mobj\x1b[0m"))),
+    ///     align_headers: Some(HeaderColumn::new()),
+    ///     ..Options::default()
+    /// };
+    /// let (flags, name) = (1u8, [0u32; 2]);
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, name; flags).unwrap();
+    /// let out = String::from_utf8(buf).unwrap();
+    /// assert!(out.contains("-----+ \x1b[1mobj\x1b[0m: u8       = flags\n"));
+    /// ```
+    pub align_headers: Option<HeaderColumn>,
+    /// Appends the name of the current thread to the header line, or its id if it has no name,
+    /// e.g. `on worker-2` or `on ThreadId(3)`, to tell apart the dumps of threads.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Options};
+    /// use std::thread;
+    ///
+    /// let options = Options {
+    ///     thread: true,
+    ///     ..Options::default()
+    /// };
+    /// let out = thread::Builder::new()
+    ///     .name("worker-2".to_owned())
+    ///     .spawn(move || {
+    ///         let v = 1u8;
+    ///         let mut buf: Vec<u8> = vec![];
+    ///         write_binspect_with!(&mut buf, &options, v).unwrap();
+    ///         String::from_utf8(buf).unwrap()
+    ///     })
+    ///     .unwrap()
+    ///     .join()
+    ///     .unwrap();
+    /// assert!(out.contains(": u8 = v on worker-2\n"));
+    /// ```
+    pub thread: bool,
+    /// Output format.
+    ///
+    /// The dumps printed in the text format by [`binspect!`](crate::binspect),
+    /// [`ebinspect!`](crate::ebinspect) and their variants use the format named by the
+    /// `BINSPECT_FORMAT` environment variable instead if it is `json` or `yaml`.
+    pub format: Format,
+    /// Appends the bytes of each line of the text format as ASCII, non-printable bytes as `.`.
+    pub ascii: bool,
+    /// Appends the values of the lanes of SIMD vectors, or of any array of numbers, starting on
+    /// each line of the text format with their indices, e.g. `lanes 0-3: 1.0, 2.0, 3.0, 4.0`.
+    ///
+    /// Lanes containing bytes which are not shown are shown as `??`.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Lane, Options};
+    /// let options = Options {
+    ///     lanes: Some(Lane::F32),
+    ///     ..Options::default()
+    /// };
+    /// // Laid out like `_mm_setr_ps(1.0, 2.0, 3.0, 4.0)` and `f32x4::from_array([1.0, 2.0, 3.0, 4.0])`.
+    /// let v = [1.0f32, 2.0, 3.0, 4.0];
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, v).unwrap();
+    /// assert!(String::from_utf8(buf)
+    ///     .unwrap()
+    ///     .ends_with(" | 00 00 80 3f 00 00 00 40 : 00 00 40 40 00 00 80 40  lanes 0-3: 1.0, 2.0, 3.0, 4.0\n"));
+    /// ```
+    pub lanes: Option<Lane>,
+    /// Decodes sequences of LEB128 varints, shown at the end of the line each varint starts on.
+    pub varints: Vec<Varints>,
+    /// Colors the bytes of the text format with ANSI escape sequences, by default by class: null
+    /// bytes, printable ASCII, ASCII whitespace, other ASCII and non-ASCII bytes (requires the
+    /// `color` feature). See [`color_theme`](Options::color_theme) for other colors.
+    ///
+    /// By default, only dumps printed to a terminal are colored, unless `NO_COLOR` is set.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, ColorChoice, Options};
+    /// let options = Options {
+    ///     color: ColorChoice::Always,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, [0u8, b'A']).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with(
+    ///     "\n0000 | \x1b[90m00\x1b[0m \x1b[36m41\x1b[0m\n"
+    /// ));
+    /// ```
+    #[cfg(feature = "color")]
+    pub color: ColorChoice,
+    /// How bytes are colored (requires the `color` feature).
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, ColorChoice, ColorTheme, Options};
+    /// let options = Options {
+    ///     color: ColorChoice::Always,
+    ///     color_theme: ColorTheme::Heatmap,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, [0x00u8, 0xff]).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with(
+    ///     "\n0000 | \x1b[38;5;232m00\x1b[0m \x1b[38;5;255mff\x1b[0m\n"
+    /// ));
+    /// ```
+    #[cfg(feature = "color")]
+    pub color_theme: ColorTheme,
+    /// Comment appended to each line of the text format.
+    pub comment: Option<Comment>,
+    /// Prints only the first line of the text format, followed by a note on how to see the rest.
+    ///
+    /// This is ignored if the `BINSPECT_FULL` environment variable is set to a value other than
+    /// `0`, so the full output is one re-run away.
+    pub collapsed: bool,
+    /// Writes dumps of the text format of at least a given size to files instead.
+    pub spill: Option<Spill>,
+    /// How addresses are rendered.
+    pub address: AddressFormat,
+    /// Resolves addresses inside regions named by [`name_region`](crate::name_region). Enabled by default.
+    pub regions: bool,
+    /// Flags words which look like pointers, and so are likely 4 bytes long on 32-bit targets such
+    /// as many embedded and `wasm32` targets. This has no effect on 32-bit hosts.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Options};
+    /// let options = Options {
+    ///     pointer_hints: true,
+    ///     ..Options::default()
+    /// };
+    /// let v = vec![1u8, 2, 3];
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, v).unwrap();
+    /// if cfg!(target_pointer_width = "64") {
+    ///     assert!(String::from_utf8(buf).unwrap().contains("  4-byte pointers on 32-bit: 00"));
+    /// }
+    /// ```
+    pub pointer_hints: bool,
+    /// Decodes tagged pointers in the bytes, shown at the end of their line with the tag.
+    pub tags: Option<TagScheme>,
+    /// Reads the bytes with aligned word-sized reads where possible instead of byte by byte, e.g.
+    /// for memory-mapped I/O registers which fault on narrower accesses.
+    ///
+    /// The bytes are copied once before rendering, so each word is read exactly once.
+    pub word_reads: bool,
+    /// Combines the bytes with a key before they are shown. The object itself is not modified,
+    /// and [`comment`](Options::comment) callbacks receive the original bytes.
+    pub mask: Option<Mask>,
+    /// A byte range to dump in full after a [`Format::Braille`] overview, for zooming into a part of
+    /// a large object. The overview marks the glyphs of the range with `^` beneath them, and the
+    /// lines of the range start at a multiple of 16 bytes as in a normal dump.
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Format, Options};
+    /// let options = Options {
+    ///     format: Format::Braille(0),
+    ///     zoom: Some(0x214..0x218),
+    ///     ..Options::default()
+    /// };
+    /// let mut heap = [0u8; 1024];
+    /// heap[0x215] = 0xab;
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, heap).unwrap();
+    /// let out = String::from_utf8(buf).unwrap();
+    /// assert!(out.contains("\n     |   ^\n     + 1 of 1024 bytes above 0x00\n"));
+    /// assert!(out.ends_with("\n     + zoom 0214..0218\n0210 | 00 00 00 00 00 ab 00 00\n"));
+    /// ```
+    pub zoom: Option<Range<usize>>,
+    /// The fields of dumped objects whose types do not implement [`Binspect`](crate::Binspect), e.g. made with
+    /// [`schema_of!`](crate::schema_of) or from [`offset_of!`](core::mem::offset_of). They are shown as for
+    /// `#[derive(Binspect)]` types.
+    pub schema: Option<schema::Schema>,
+    /// Writes a legend row beneath each line of a dump with fields, with a letter per byte for the
+    /// field it belongs to and `.` for padding. The letters are shown in the field map too.
+    ///
+    /// ```
+    /// # use binspect::schema::{Encoding, Schema};
+    /// # use binspect::{write_binspect_with, Options};
+    /// let options = Options {
+    ///     schema: Some(
+    ///         Schema::new()
+    ///             .field("x", 0, 4, Encoding::Bytes)
+    ///             .field("y", 8, 4, Encoding::Bytes)
+    ///             .field("z", 12, 4, Encoding::Bytes),
+    ///     ),
+    ///     legend: true,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, [0x11u8; 16]).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with(
+    ///     "\n0000 | 11 11 11 11 11 11 11 11 : 11 11 11 11 11 11 11 11\n\
+    ///      \x20    | aaaa....bbbbcccc\n\
+    ///      \x20    + a 0000..0004 x: [u8; 4]\n\
+    ///      \x20    + b 0008..000c y: [u8; 4]\n\
+    ///      \x20    + c 000c..0010 z: [u8; 4]\n"
+    /// ));
+    /// ```
+    pub legend: bool,
+    /// Shows the bytes of a dump with fields which belong to no field as `--`, and counts them
+    /// after the field map, to see the padding of a type at a glance.
+    ///
+    /// ```
+    /// # use binspect::schema_of;
+    /// # use binspect::{write_binspect_with, Options};
+    /// #[repr(C)]
+    /// struct Padded {
+    ///     flag: u8,
+    ///     value: u32,
+    /// }
+    ///
+    /// let v = Padded { flag: 1, value: 2 };
+    /// let options = Options {
+    ///     schema: Some(schema_of!(v, flag, value)),
+    ///     padding: true,
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, v).unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with(
+    ///     "\n0000 | 01 -- -- -- 02 00 00 00\n\
+    ///      \x20    + 0000..0001 flag: [u8; 1]\n\
+    ///      \x20    + 0004..0008 value: [u8; 4]\n\
+    ///      \x20    + 3 bytes of padding\n"
+    /// ));
+    /// ```
+    pub padding: bool,
+    /// Shows the bytes equal to the given poison byte as `~~`, and counts the other bytes after
+    /// the lines, to see which bytes of a buffer filled by [`poison`](crate::poison) a routine wrote. Bytes which
+    /// were written with the poison byte itself are shown as not written.
+    ///
+    /// ```
+    /// # use binspect::{poison, write_binspect_with, Options, POISON};
+    /// let mut buf = [0u8; 8];
+    /// poison(&mut buf);
+    /// buf[2..4].copy_from_slice(&[0, 7]);
+    /// let options = Options {
+    ///     poison: Some(POISON),
+    ///     ..Options::default()
+    /// };
+    /// let mut out: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut out, &options, buf).unwrap();
+    /// assert!(String::from_utf8(out).unwrap().ends_with(
+    ///     "\n0000 | ~~ ~~ 00 07 ~~ ~~ ~~ ~~\n     + 2 of 8 bytes written\n"
+    /// ));
+    /// ```
+    pub poison: Option<u8>,
+    /// Shows the bytes equal to the byte at the same offset of the given copy as `~~`, and counts
+    /// the other bytes after the lines, like [`poison`](Options::poison) does. See
+    /// [`ShadowTracked`](crate::ShadowTracked).
+    pub shadow: Option<Vec<u8>>,
+    /// Adds the time of each dump in microseconds since the Unix epoch to JSON output as `time`,
+    /// so that the logs of several processes can be interleaved with [`Merge`](crate::Merge).
+    ///
+    /// ```
+    /// # use binspect::{write_binspect_with, Format, Merge, Options};
+    /// let options = Options {
+    ///     format: Format::Json,
+    ///     timestamps: true,
+    ///     ..Options::default()
+    /// };
+    /// let v = 7u8;
+    /// let mut log: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut log, &options, v).unwrap();
+    /// let log = String::from_utf8(log).unwrap();
+    /// assert!(log.contains(",\"time\":"));
+    /// let mut merge = Merge::new();
+    /// merge.add("main", &log).unwrap();
+    /// let mut out: Vec<u8> = vec![];
+    /// merge.write(&mut out).unwrap();
+    /// assert!(String::from_utf8(out).unwrap().ends_with(": u8 = v [main +0.000000s]\n0000 | 07\n"));
+    /// ```
+    pub timestamps: bool,
+    /// Writes the given title as the header line of the text format instead of the address, the
+    /// type and the source, e.g. for bytes which are not an object, or leaves out the header if
+    /// the title is empty. [`hexdump`] writes the title too.
+    ///
+    /// ```
+    /// # use binspect::{hexdump, write_binspect_with, Options};
+    /// let options = Options {
+    ///     title: Some("== reply from 10.0.0.2 ==".to_owned()),
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// hexdump(&mut buf, b"\x00\x01OK", &options).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "== reply from 10.0.0.2 ==\n0000 | 00 01 4f 4b\n"
+    /// );
+    ///
+    /// let options = Options {
+    ///     title: Some(String::new()),
+    ///     ..Options::default()
+    /// };
+    /// let mut buf: Vec<u8> = vec![];
+    /// write_binspect_with!(&mut buf, &options, [1u8, 2]).unwrap();
+    /// assert_eq!(String::from_utf8(buf).unwrap(), "0000 | 01 02\n");
+    /// ```
+    pub title: Option<String>,
+    /// Emits a `tracing` event with the address, the type and the source of each dump at the
+    /// `binspect` target (requires the `tracing` feature), so that dumps can be located on the
+    /// timeline of a profiler, e.g. when diagnosing false sharing.
+    ///
+    /// ```
+    /// # use binspect::{binspect_with, Options};
+    /// let options = Options {
+    ///     marker: true,
+    ///     ..Options::default()
+    /// };
+    /// let counters = [0u64; 8];
+    /// binspect_with!(&options, counters);
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub marker: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            absolute: false,
+            align_rows: false,
+            placeholders: false,
+            header: Header::default(),
+            location: false,
+            align_headers: None,
+            thread: false,
+            format: Format::default(),
+            ascii: false,
+            lanes: None,
+            varints: vec![],
+            #[cfg(feature = "color")]
+            color: ColorChoice::default(),
+            #[cfg(feature = "color")]
+            color_theme: ColorTheme::default(),
+            comment: None,
+            collapsed: false,
+            spill: None,
+            address: AddressFormat::default(),
+            regions: true,
+            pointer_hints: false,
+            tags: None,
+            word_reads: false,
+            mask: None,
+            zoom: None,
+            schema: None,
+            legend: false,
+            padding: false,
+            poison: None,
+            shadow: None,
+            timestamps: false,
+            title: None,
+            #[cfg(feature = "tracing")]
+            marker: false,
+        }
+    }
+}
+
+impl fmt::Debug for Options {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut d = f.debug_struct("Options");
+        d.field("absolute", &self.absolute)
+            .field("align_rows", &self.align_rows)
+            .field("placeholders", &self.placeholders)
+            .field("header", &self.header)
+            .field("location", &self.location)
+            .field("align_headers", &self.align_headers)
+            .field("thread", &self.thread)
+            .field("format", &self.format)
+            .field("ascii", &self.ascii)
+            .field("lanes", &self.lanes)
+            .field("varints", &self.varints)
+            .field("comment", &self.comment.as_ref().map(|_| ".."))
+            .field("collapsed", &self.collapsed)
+            .field("spill", &self.spill)
+            .field("address", &self.address)
+            .field("regions", &self.regions)
+            .field("pointer_hints", &self.pointer_hints)
+            .field("tags", &self.tags)
+            .field("word_reads", &self.word_reads);
+        #[cfg(feature = "color")]
+        d.field("color", &self.color)
+            .field("color_theme", &self.color_theme);
+        #[cfg(feature = "tracing")]
+        d.field("marker", &self.marker);
+        d.field("mask", &self.mask)
+            .field("zoom", &self.zoom)
+            .field("schema", &self.schema)
+            .field("legend", &self.legend)
+            .field("padding", &self.padding)
+            .field("poison", &self.poison)
+            .field("shadow", &self.shadow)
+            .field("timestamps", &self.timestamps)
+            .field("title", &self.title)
+            .finish()
+    }
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn doc_options() -> Options {
+    Options {
+        placeholders: true,
+        ..Options::default()
+    }
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn abs_options() -> Options {
+    Options {
+        absolute: true,
+        ..Options::default()
+    }
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn json_options() -> Options {
+    Options {
+        format: Format::Json,
+        ..Options::default()
+    }
+}
+
+pub(crate) fn write_lines<W: Write>(
+    w: &mut W,
+    bytes: &[u8],
+    offset: usize,
+    address: Option<*const u8>,
+    options: &Options,
+) -> Result<(), io::Error> {
+    write_lines_with(w, bytes, offset, address, options, &[], None)
+}
+
+/// Writes lines like [`write_lines`], showing the bytes in the `unreadable` ranges as `??` and the
+/// bytes of hidden fields of `schema` as `**` or `..`.
+pub(crate) fn write_lines_with<W: Write>(
+    w: &mut W,
+    bytes: &[u8],
+    offset: usize,
+    address: Option<*const u8>,
+    options: &Options,
+    unreadable: &[Range<usize>],
+    schema: Option<&schema::Schema>,
+) -> Result<(), io::Error> {
+    let center = WIDTH / 2;
+    let varints = varint::decode(bytes, offset, &options.varints);
+    // The number of empty cells before the first byte, to align the columns to the address.
+    let lead = match address {
+        Some(p) if options.align_rows && offset == 0 => p as usize % WIDTH,
+        _ => 0,
+    };
+    let mut next = 0;
+    while next < bytes.len() {
+        let (start, pad) = (next, if next == 0 { lead } else { 0 });
+        let row = &bytes[start..bytes.len().min(start + WIDTH - pad)];
+        next += row.len();
+        match address {
+            Some(p) => write!(
+                w,
+                "{} |",
+                Address(p.wrapping_add(start).wrapping_sub(pad), options)
+            )?,
+            None => write!(w, "{:04x} |", offset + start)?,
+        }
+        let shown = |j: usize| {
+            if is_unreadable(unreadable, start + j) {
+                Err("??")
+            } else if let Some(s) = hidden(schema, offset + start + j) {
+                Err(s)
+            } else if options.padding && is_padding(schema, offset + start + j) {
+                Err("--")
+            } else if is_untouched(options, offset + start + j, row[j]) {
+                Err("~~")
+            } else if options.placeholders && is_masked(bytes, start + j) {
+                Err("##")
+            } else {
+                Ok(match &options.mask {
+                    Some(mask) => mask.apply(offset + start + j, row[j]),
+                    None => row[j],
+                })
+            }
+        };
+        for j in 0..pad {
+            if j == center {
+                write!(w, " :")?;
+            }
+            write!(w, "   ")?;
+        }
+        for j in 0..row.len() {
+            if pad + j == center {
+                write!(w, " :")?;
+            }
+            match shown(j) {
+                #[cfg(feature = "color")]
+                Ok(x) if options.color == ColorChoice::Always => write!(
+                    w,
+                    " {}{:02x}{}",
+                    color::Start(options.color_theme, x),
+                    x,
+                    color::RESET
+                )?,
+                Ok(x) => write!(w, " {:02x}", x)?,
+                Err(s) => write!(w, " {}", s)?,
+            }
+        }
+        let cells = pad + row.len();
+        let mut padding = (WIDTH - cells) * 3 + if cells <= center { 2 } else { 0 };
+        if options.ascii {
+            write!(w, "{:width$}  {:pad$}", "", "", width = padding, pad = pad)?;
+            for j in 0..row.len() {
+                let c = match shown(j) {
+                    Ok(x) if x.is_ascii_graphic() || x == b' ' => x as char,
+                    Ok(_) => '.',
+                    Err(s) => s.as_bytes()[0] as char,
+                };
+                #[cfg(feature = "color")]
+                {
+                    if let (ColorChoice::Always, Ok(x)) = (options.color, shown(j)) {
+                        write!(
+                            w,
+                            "{}{}{}",
+                            color::Start(options.color_theme, x),
+                            c,
+                            color::RESET
+                        )?;
+                        continue;
+                    }
+                }
+                write!(w, "{}", c)?;
+            }
+            padding = WIDTH - cells;
+        }
+        let mut separator = "";
+        if let Some(lane) = options.lanes {
+            let size = lane.size();
+            let first = (start + size - 1) / size;
+            let last = (start + row.len()) / size;
+            if first < last {
+                write_note(w, &mut separator, padding)?;
+                write!(w, "lanes {}-{}:", first, last - 1)?;
+                for k in first..last {
+                    let shown: Result<Vec<u8>, _> = (k * size..(k + 1) * size)
+                        .map(|i| shown(i - start))
+                        .collect();
+                    if k > first {
+                        write!(w, ",")?;
+                    }
+                    match shown {
+                        Ok(value) => {
+                            write!(w, " ")?;
+                            lane.write(w, &value)?
+                        }
+                        Err(_) => write!(w, " ??")?,
+                    }
+                }
+            }
+        }
+        let line = offset + start..offset + start + row.len();
+        for (extent, value) in varints.iter().filter(|(e, _)| line.contains(&e.start)) {
+            write_note(w, &mut separator, padding)?;
+            write!(
+                w,
+                "varint {:04x}..{:04x} = {}",
+                extent.start, extent.end, value
+            )?;
+        }
+        if let Some(comment) = options
+            .comment
+            .as_ref()
+            .and_then(|f| f(offset + start, row))
+        {
+            write_note(w, &mut separator, padding)?;
+            write!(w, "{}", comment)?;
+        }
+        if (start..start + row.len()).any(|i| is_unreadable(unreadable, i)) {
+            write_note(w, &mut separator, padding)?;
+            write!(w, "(unmapped)")?;
+        }
+        if options.regions || options.tags.is_some() {
+            for word in row.chunks(mem::size_of::<usize>()) {
+                if word.len() < mem::size_of::<usize>() {
+                    break;
+                }
+                let mut buf = [0u8; mem::size_of::<usize>()];
+                buf.copy_from_slice(word);
+                let value = usize::from_ne_bytes(buf);
+                let (value, tag) = match options.tags {
+                    Some(tags) => tags.decode(value),
+                    None => (value, 0),
+                };
+                let region = if options.regions {
+                    region::find(value)
+                } else {
+                    None
+                };
+                if region.is_none() && (tag == 0 || !is_user_address(value)) {
+                    continue;
+                }
+                write_note(w, &mut separator, padding)?;
+                match region {
+                    Some(region) => write!(w, "-> {}", region)?,
+                    None => write!(w, "-> {}", Address(value as *const u8, options))?,
+                }
+                if tag != 0 {
+                    write!(w, " tag {:#x}", tag)?;
+                }
+            }
+        }
+        if options.pointer_hints && mem::size_of::<usize>() > 4 {
+            let mut first = true;
+            for k in (0..row.len()).step_by(mem::size_of::<usize>()) {
+                if looks_like_pointer(&row[k..]) {
+                    if first {
+                        write_note(w, &mut separator, padding)?;
+                        write!(w, "4-byte pointers on 32-bit: {:04x}", offset + start + k)?;
+                        first = false;
+                    } else {
+                        write!(w, ", {:04x}", offset + start + k)?;
+                    }
+                }
+            }
+        }
+        writeln!(w)?;
+        if let (true, Some(schema)) = (options.legend, schema) {
+            write!(w, "     | {:pad$}", "", pad = pad)?;
+            for i in offset + start..offset + start + row.len() {
+                match schema
+                    .fields()
+                    .iter()
+                    .position(|f| f.offset <= i && i < f.end())
+                {
+                    Some(k) => write!(w, "{}", legend_letter(k))?,
+                    None => write!(w, ".")?,
+                }
+            }
+            writeln!(w)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the letter of the `k`-th field in legend rows: `a` to `z`, `A` to `Z`, then `#`.
+fn legend_letter(k: usize) -> char {
+    match k {
+        0..=25 => (b'a' + k as u8) as char,
+        26..=51 => (b'A' + (k - 26) as u8) as char,
+        _ => '#',
+    }
+}
+
+/// Writes the padding or the separator before a note at the end of a line.
+fn write_note<W: Write>(w: &mut W, separator: &mut &str, padding: usize) -> Result<(), io::Error> {
+    match *separator {
+        "" => write!(w, "{:width$}  ", "", width = padding)?,
+        s => write!(w, "{} ", s)?,
+    }
+    *separator = ",";
+    Ok(())
+}
+
+/// Copies `bytes` with volatile reads of aligned words, and of single bytes only before the first
+/// and after the last aligned word.
+fn read_words(bytes: &[u8]) -> Vec<u8> {
+    const SIZE: usize = mem::size_of::<usize>();
+    let p = bytes.as_ptr();
+    let head = p.align_offset(SIZE).min(bytes.len());
+    let words = (bytes.len() - head) / SIZE;
+    let mut buf = Vec::with_capacity(bytes.len());
+    unsafe {
+        for i in 0..head {
+            buf.push(ptr::read_volatile(p.add(i)));
+        }
+        for i in 0..words {
+            let word = ptr::read_volatile(p.add(head + i * SIZE) as *const usize);
+            buf.extend_from_slice(&word.to_ne_bytes());
+        }
+        for i in head + words * SIZE..bytes.len() {
+            buf.push(ptr::read_volatile(p.add(i)));
+        }
+    }
+    buf
+}
+
+pub(crate) fn is_unreadable(unreadable: &[Range<usize>], i: usize) -> bool {
+    unreadable.iter().any(|r| r.start <= i && i < r.end)
+}
+
+/// Returns how the `i`-th byte is shown if it belongs to a redacted or skipped field of `schema`.
+pub(crate) fn hidden(schema: Option<&schema::Schema>, i: usize) -> Option<&'static str> {
+    let field = schema?
+        .fields()
+        .iter()
+        .find(|f| f.offset <= i && i < f.end() && f.encoding != schema::Encoding::Bytes)?;
+    match field.encoding {
+        schema::Encoding::Redacted => Some("**"),
+        schema::Encoding::Skipped => Some(".."),
+        _ => None,
+    }
+}
+
+/// Returns whether the `i`-th byte `x` still equals the poison byte or the shadow copy.
+fn is_untouched(options: &Options, i: usize, x: u8) -> bool {
+    options.poison == Some(x) || options.shadow.as_ref().and_then(|s| s.get(i)) == Some(&x)
+}
+
+/// Returns whether the `i`-th byte belongs to no field of `schema`.
+fn is_padding(schema: Option<&schema::Schema>, i: usize) -> bool {
+    match schema {
+        Some(schema) => !schema.fields().iter().any(|f| f.offset <= i && i < f.end()),
+        None => false,
+    }
+}
+
+/// Writes a line per field of `schema` with its range, type and value.
+fn write_fields<W: Write>(
+    w: &mut W,
+    schema: &schema::Schema,
+    bytes: &[u8],
+    options: &Options,
+) -> Result<(), io::Error> {
+    for (k, field) in schema.fields().iter().enumerate() {
+        let data = &bytes[field.offset.min(bytes.len())..field.end().min(bytes.len())];
+        let masked = options.placeholders
+            && (field.offset..field.offset + data.len()).any(|i| is_masked(bytes, i));
+        let note = if masked {
+            field.to_string()
+        } else {
+            field.note(data)
+        };
+        write!(w, "     + ")?;
+        if options.legend {
+            write!(w, "{} ", legend_letter(k))?;
+        }
+        writeln!(w, "{:04x}..{:04x} {}", field.offset, field.end(), note)?;
+    }
+    if options.padding {
+        let padding = (0..bytes.len())
+            .filter(|&i| is_padding(Some(schema), i))
+            .count();
+        writeln!(w, "     + {} bytes of padding", padding)?;
+    }
+    Ok(())
+}
+
+/// Returns whether the `i`-th byte belongs to an aligned word which looks like a pointer.
+pub(crate) fn is_masked(bytes: &[u8], i: usize) -> bool {
+    looks_like_pointer(&bytes[i - i % mem::size_of::<usize>()..])
+}
+
+/// Returns whether the pointer-sized word at the start of `bytes` looks like a user-space address.
+fn looks_like_pointer(bytes: &[u8]) -> bool {
+    const SIZE: usize = mem::size_of::<usize>();
+    if bytes.len() < SIZE {
+        return false;
+    }
+    let mut buf = [0u8; SIZE];
+    buf.copy_from_slice(&bytes[..SIZE]);
+    is_user_address(usize::from_ne_bytes(buf))
+}
+
+/// Returns whether `value` looks like a user-space address.
+pub(crate) fn is_user_address(value: usize) -> bool {
+    const SIZE: usize = mem::size_of::<usize>();
+    let high = if SIZE == 8 {
+        1 << 47
+    } else {
+        usize::max_value()
+    };
+    0x1_0000 <= value && value < high
+}
+
+/// Writes a value of a `key=value` pair, quoted if needed or `quote` is set.
+pub(crate) fn write_value<W: Write>(w: &mut W, value: &str, quote: bool) -> Result<(), io::Error> {
+    let special =
+        |c: char| c.is_whitespace() || c.is_control() || c == '=' || c == '"' || c == '\\';
+    if !quote && !value.is_empty() && !value.contains(special) {
+        return write!(w, "{}", value);
+    }
+    write!(w, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => write!(w, "\\{}", c)?,
+            c if c.is_control() => write!(w, "{}", c.escape_default())?,
+            _ => write!(w, "{}", c)?,
+        }
+    }
+    write!(w, "\"")
+}
+
+/// Writes a key of a `key=value` pair, replacing characters which would break the pair by `_`.
+fn write_key<W: Write>(w: &mut W, key: &str) -> Result<(), io::Error> {
+    for c in key.chars() {
+        let c = if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' {
+            c
+        } else {
+            '_'
+        };
+        write!(w, "{}", c)?;
+    }
+    Ok(())
+}
+
+#[doc(hidden)]
+pub fn write_internal<W: Write, T: ?Sized, R: Renderer + ?Sized>(
+    mut w: W,
+    record: &Record<T>,
+    renderer: &R,
+) -> Result<(), io::Error> {
+    renderer.render(&mut w, &record.info(), record.bytes)
+}
+
+/// Writes the lines of the hex dump of `bytes` in the text format with the given options, as the
+/// macros do after the header, e.g. for bytes read from a file or received from the network. The
+/// lines are preceded by [`Options::title`] if given.
+///
+/// Lines start with offsets from the start of `bytes`, or with addresses with
+/// [`Options::absolute`]. The fields of [`Options::schema`] are listed under the lines. Any
+/// `io::Write` can be passed, e.g. `&mut Vec<u8>`, a locked stdout or a file, and [`write_hex`](crate::write_hex)
+/// writes the lines with the default options to `fmt::Write`.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{hexdump, Options};
+///
+/// let packet = b"\x01\x02GET /index.html";
+/// let options = Options {
+///     ascii: true,
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// hexdump(&mut buf, packet, &options).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "0000 | 01 02 47 45 54 20 2f 69 : 6e 64 65 78 2e 68 74 6d  ..GET /index.htm\n\
+///      0010 | 6c                                                 l\n"
+/// );
+/// ```
+pub fn hexdump<W: Write>(mut w: W, bytes: &[u8], options: &Options) -> Result<(), io::Error> {
+    let info = DumpInfo {
+        address: bytes.as_ptr(),
+        type_name: type_name::<[u8]>(),
+        size: bytes.len(),
+        source: "",
+        label: None,
+        file: "",
+        line: 0,
+        column: 0,
+        schema: options.schema.as_ref(),
+        meta: &[],
+    };
+    if options.title.is_some() {
+        write_header(&mut w, &info, options)?;
+    }
+    write_body(w, &info, bytes, options, &[])
+}
+
+/// The renderer of the built-in formats.
+impl Renderer for Options {
+    fn header(&self, mut w: &mut dyn Write, info: &DumpInfo) -> Result<(), io::Error> {
+        write_header(&mut w, info, self)
+    }
+
+    fn line(
+        &self,
+        mut w: &mut dyn Write,
+        info: &DumpInfo,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), io::Error> {
+        let address = line_address(info, self).map(|p| p.wrapping_add(offset));
+        write_lines_with(&mut w, bytes, offset, address, self, &[], info.schema)
+    }
+
+    fn render(&self, w: &mut dyn Write, info: &DumpInfo, bytes: &[u8]) -> Result<(), io::Error> {
+        #[cfg(feature = "tracing")]
+        {
+            if self.marker {
+                trace::emit(info);
+            }
+        }
+        write_record(w, info, bytes, self, &[])
+    }
+}
+
+/// Returns the address shown at the start of lines instead of the offset, if any.
+pub(crate) fn line_address(info: &DumpInfo, options: &Options) -> Option<*const u8> {
+    if options.absolute && !options.placeholders && !options.address.is_scrubbed() {
+        Some(info.address)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn write_record<W: Write>(
+    mut w: W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+) -> Result<(), io::Error> {
+    if let (None, Some(schema)) = (info.schema, &options.schema) {
+        let info = DumpInfo {
+            schema: Some(schema),
+            ..*info
+        };
+        return write_record(w, &info, bytes, options, unreadable);
+    }
+    if options.word_reads {
+        let bytes = read_words(bytes);
+        let options = Options {
+            word_reads: false,
+            ..options.clone()
+        };
+        return write_record(w, info, &bytes, &options, unreadable);
+    }
+    match options.format {
+        Format::Text => {}
+        Format::Yaml => return yaml::write_yaml(w, info, bytes, options, unreadable),
+        Format::Json => return json::write_json(w, info, bytes, options, unreadable),
+        Format::Bitmap(width) => {
+            return bitmap::write_bitmap(w, info, bytes, options, unreadable, width);
+        }
+        Format::Braille(threshold) => {
+            return braille::write_braille(w, info, bytes, options, unreadable, threshold);
+        }
+    }
+    if let Some(spill) = &options.spill {
+        if bytes.len() >= spill.threshold {
+            let options = Options {
+                spill: None,
+                ..options.clone()
+            };
+            let path = spill.create(info, bytes, |f| {
+                write_record(f, info, bytes, &options, unreadable)
+            })?;
+            write_header(&mut w, info, &options)?;
+            return writeln!(
+                w,
+                "     + {} bytes written to {}",
+                bytes.len(),
+                path.display()
+            );
+        }
+    }
+    write_header(&mut w, info, options)?;
+    write_body(w, info, bytes, options, unreadable)
+}
+
+/// Writes the lines of a dump in the text format and the notes under them.
+fn write_body<W: Write>(
+    mut w: W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+) -> Result<(), io::Error> {
+    let address = line_address(info, options);
+    if options.collapsed && bytes.len() > WIDTH && !full_from_env() {
+        let head = &bytes[..WIDTH];
+        write_lines_with(&mut w, head, 0, address, options, unreadable, info.schema)?;
+        writeln!(
+            w,
+            "     + {} more bytes, set BINSPECT_FULL=1 to show all",
+            bytes.len() - WIDTH
+        )?;
+    } else {
+        write_lines_with(&mut w, bytes, 0, address, options, unreadable, info.schema)?;
+    }
+    if options.poison.is_some() || options.shadow.is_some() {
+        let written = (0..bytes.len())
+            .filter(|&i| !is_untouched(options, i, bytes[i]))
+            .count();
+        writeln!(w, "     + {} of {} bytes written", written, bytes.len())?;
+    }
+    match info.schema {
+        Some(schema) => write_fields(&mut w, schema, bytes, options),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn write_header<W: Write>(
+    w: &mut W,
+    info: &DumpInfo,
+    options: &Options,
+) -> Result<(), io::Error> {
+    match options.title.as_ref() {
+        Some(title) if title.is_empty() => return Ok(()),
+        Some(title) => return writeln!(w, "{}", title),
+        None => {}
+    }
+    let address = Address(info.address, options);
+    let region = if options.regions {
+        region::find(address.0 as usize)
+    } else {
+        None
+    };
+    match options.header {
+        Header::KeyValue => {
+            write!(w, "addr={}", address)?;
+            if let Some(region) = region {
+                write!(w, " region=")?;
+                write_value(w, &region, true)?;
+            }
+            write!(w, " type=")?;
+            write_value(w, info.type_name, false)?;
+            write!(w, " size={} src=", info.size)?;
+            write_value(w, info.source, true)?;
+            if let Some(label) = info.label {
+                write!(w, " label=")?;
+                write_value(w, label, false)?;
+            }
+            for (key, value) in info.meta {
+                write!(w, " meta.")?;
+                write_key(w, key)?;
+                write!(w, "=")?;
+                write_value(w, &value.to_string(), false)?;
+            }
+            if options.thread {
+                write!(w, " thread=")?;
+                write_value(w, &thread_name(), false)?;
+            }
+            write!(w, " file=")?;
+            write_value(w, info.file, false)?;
+            return writeln!(w, ":{}:{}", info.line, info.column);
+        }
+        Header::Compact => {
+            write!(w, "{}: {} @ {}", info.source, info.type_name, address)?;
+            if let Some(region) = region {
+                write!(w, " ({})", region)?;
+            }
+        }
+        Header::Classic | Header::Verbose => {
+            if let Some(aligned) = &options.align_headers {
+                let mut head: Vec<u8> = vec![];
+                write_head(&mut head, &address, region, info, options)?;
+                let width = display_width(&head);
+                let column = aligned.align(width);
+                w.write_all(&head)?;
+                write!(w, "{:pad$}", "", pad = column - width)?;
+            } else {
+                write_head(w, &address, region, info, options)?;
+            }
+            write!(w, " = {}", info.source)?;
+        }
+    }
+    if let Some(label) = info.label {
+        write!(w, " [{}]", label)?;
+    }
+    for (i, (key, value)) in info.meta.iter().enumerate() {
+        let open = if i == 0 { " {" } else { ", " };
+        write!(w, "{}{}: {}", open, key, value)?;
+    }
+    if !info.meta.is_empty() {
+        write!(w, "}}")?;
+    }
+    if options.thread {
+        write!(w, " on {}", thread_name())?;
+    }
+    if options.header == Header::Verbose {
+        let unit = if info.size == 1 { "byte" } else { "bytes" };
+        write!(w, " ({} {})", info.size, unit)?;
+    }
+    if options.location || options.header == Header::Verbose {
+        write!(w, " at {}:{}:{}", info.file, info.line, info.column)?;
+    }
+    writeln!(w)
+}
+
+/// Returns the name of the current thread, or its id if it has no name.
+fn thread_name() -> String {
+    let thread = std::thread::current();
+    match thread.name() {
+        Some(name) => name.to_owned(),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
+/// Writes the classic header up to the type, before ` = `.
+fn write_head<W: Write>(
+    w: &mut W,
+    address: &Address,
+    region: Option<String>,
+    info: &DumpInfo,
+    options: &Options,
+) -> Result<(), io::Error> {
+    if options.absolute {
+        write!(w, "{}", address)?;
+    } else {
+        write!(w, "-----+ {}", address)?;
+    }
+    if let Some(region) = region {
+        write!(w, " ({})", region)?;
+    }
+    let separator = if options.absolute { " :" } else { ":" };
+    write!(w, "{} {}", separator, info.type_name)
+}
+
+const MAX_ALIGNED: usize = 60;
+
+/// The column at which the `=` signs of the headers line up with
+/// [`Options::align_headers`]: the widest head aligned so far, up to a column of 60. Clones share
+/// the column, so that the dumps of a single options value line up while others are unaffected.
+#[derive(Clone, Debug, Default)]
+pub struct HeaderColumn(Arc<AtomicUsize>);
+
+impl HeaderColumn {
+    /// Returns a column which no header has widened yet.
+    pub fn new() -> HeaderColumn {
+        HeaderColumn::default()
+    }
+
+    /// Returns the column at which ` = ` follows a head of `width` characters, so that it lines
+    /// up with the previous aligned headers.
+    fn align(&self, width: usize) -> usize {
+        if width > MAX_ALIGNED {
+            return width;
+        }
+        let widest = self.0.load(Ordering::SeqCst);
+        if width > widest {
+            // Racing dumps may lose an update, which only misaligns a header.
+            self.0.store(width, Ordering::SeqCst);
+        }
+        widest.max(width)
+    }
+}
+
+/// Returns the number of characters of `head` shown on a terminal, without ANSI escape
+/// sequences, e.g. colors written by an [`AddressFormat::Custom`].
+fn display_width(head: &[u8]) -> usize {
+    let head = String::from_utf8_lossy(head);
+    let mut chars = head.chars();
+    let mut width = 0;
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            width += 1;
+        } else if chars.next() == Some('[') {
+            // The parameters of a control sequence end at a byte in `@..=~`.
+            chars.find(|c| ('@'..='~').contains(c));
+        }
+    }
+    width
+}
+
+fn full_from_env() -> bool {
+    match std::env::var_os("BINSPECT_FULL") {
+        Some(v) => !v.is_empty() && v != "0",
+        None => false,
+    }
+}
+
+/// Parses the value of `BINSPECT_FORMAT`.
+pub(crate) fn parse_format(s: &str) -> Option<Format> {
+    match s {
+        "text" => Some(Format::Text),
+        "json" => Some(Format::Json),
+        "yaml" => Some(Format::Yaml),
+        _ => None,
+    }
+}
+
+/// Returns the options with the format of `BINSPECT_FORMAT` if they use the text format.
+fn for_env(options: &Options) -> Cow<'_, Options> {
+    let format = std::env::var("BINSPECT_FORMAT").ok();
+    match format.as_deref().and_then(parse_format) {
+        Some(format) if options.format == Format::Text && format != Format::Text => {
+            Cow::Owned(Options {
+                format,
+                ..options.clone()
+            })
+        }
+        _ => Cow::Borrowed(options),
+    }
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn print_internal<T: ?Sized>(record: &Record<T>, options: &Options) {
+    if !filter::is_shown(type_name::<T>()) {
+        return;
+    }
+    let options: &Options = &for_env(options);
+    #[cfg(feature = "color")]
+    let options: &Options = &for_stream(options, color::Stream::Stdout);
+    limit::limited(io::stdout(), |w| write_internal(w, record, options)).unwrap()
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn eprint_internal<T: ?Sized>(record: &Record<T>, options: &Options) {
+    if !filter::is_shown(type_name::<T>()) {
+        return;
+    }
+    let options: &Options = &for_env(options);
+    #[cfg(feature = "color")]
+    let options: &Options = &for_stream(options, color::Stream::Stderr);
+    limit::limited(io::stderr(), |w| write_internal(w, record, options)).unwrap()
+}
+
+/// Returns the options with the color resolved for output printed to `stream`.
+#[cfg(feature = "color")]
+pub(crate) fn for_stream(options: &Options, stream: color::Stream) -> Cow<'_, Options> {
+    match color::resolve(options.color, stream) {
+        c if c == options.color => Cow::Borrowed(options),
+        color => Cow::Owned(Options {
+            color,
+            ..options.clone()
+        }),
+    }
+}
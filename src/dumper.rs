@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+
+use crate::{write_lines, Options};
+
+/// Renders byte chunks fed over time with a continuous offset column.
+///
+/// Each chunk starts on a new line, but its offsets continue from the end of the previous chunk,
+/// so the output of a stream can be matched against the offsets of the whole stream.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::Dumper;
+///
+/// let mut dumper = Dumper::new(vec![]).headers(true);
+/// dumper.feed(b"GET / HTTP/1.1\r\n").unwrap();
+/// dumper.feed(b"\r\n").unwrap();
+/// assert_eq!(dumper.offset(), 18);
+/// let out = String::from_utf8(dumper.into_inner()).unwrap();
+/// assert_eq!(
+///     out,
+///     "-----+ chunk 0 (16 bytes)\n\
+///      0000 | 47 45 54 20 2f 20 48 54 : 54 50 2f 31 2e 31 0d 0a\n\
+///      -----+ chunk 1 (2 bytes)\n\
+///      0010 | 0d 0a\n"
+/// );
+/// ```
+pub struct Dumper<W: Write> {
+    w: W,
+    offset: usize,
+    chunks: usize,
+    headers: bool,
+}
+
+impl<W: Write> Dumper<W> {
+    /// Creates a dumper writing to `w`, starting at offset 0.
+    pub fn new(w: W) -> Dumper<W> {
+        Dumper {
+            w,
+            offset: 0,
+            chunks: 0,
+            headers: false,
+        }
+    }
+
+    /// Sets whether a header line is written before each chunk.
+    pub fn headers(mut self, headers: bool) -> Dumper<W> {
+        self.headers = headers;
+        self
+    }
+
+    /// Returns the offset at which the next chunk starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of chunks fed so far.
+    pub fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    /// Renders `chunk` and advances the offset by its length.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), io::Error> {
+        if self.headers {
+            writeln!(
+                self.w,
+                "-----+ chunk {} ({} bytes)",
+                self.chunks,
+                chunk.len()
+            )?;
+        }
+        write_lines(&mut self.w, chunk, self.offset, None, &Options::default())?;
+        self.offset += chunk.len();
+        self.chunks += 1;
+        Ok(())
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.w
+    }
+
+    /// Consumes the dumper and returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
@@ -0,0 +1,40 @@
+/// Logs the memory address and the hex representation of an object with `defmt`, for
+/// microcontrollers without stdout (requires the `defmt` feature).
+///
+/// The dump is a single `info` message with the header of [`binspect!`](crate::binspect) and the
+/// bytes as a list, which the host decodes and prints, e.g. with `probe-rs`:
+///
+/// ```text
+/// -----+ 0x20000ff0: [u16; 2] = regs
+/// [0x01, 0x00, 0xff, 0x00]
+/// ```
+///
+/// The expansion uses `core` only.
+///
+/// # Examples
+///
+/// ```ignore
+/// use binspect::defmt_binspect;
+///
+/// let regs = [1u16, 255];
+/// defmt_binspect!(regs);
+/// ```
+#[macro_export]
+macro_rules! defmt_binspect {
+    ($v: expr) => {
+        match &$v {
+            t => $crate::defmt::info!(
+                "-----+ {=usize:#010x}: {=str} = {=str}\n{=[u8]:#04x}",
+                t as *const _ as *const u8 as usize,
+                $crate::type_name_of(t),
+                stringify!($v),
+                unsafe {
+                    ::core::slice::from_raw_parts(
+                        t as *const _ as *const u8,
+                        ::core::mem::size_of_val(t),
+                    )
+                }
+            ),
+        }
+    };
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Whether any rule was added, so that dumps do not lock the rules while there are none.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+global! {
+    /// The rules added by [`hide_types`] and [`show_types`], as patterns and whether they show.
+    fn rules() -> &'static Mutex<Vec<(String, bool)>> = Mutex::new(vec![]);
+}
+
+fn lock() -> MutexGuard<'static, Vec<(String, bool)>> {
+    // The rules are only pushed and cleared under the lock, so the poison is ignored.
+    rules().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn add(pattern: &str, show: bool) {
+    lock().push((pattern.to_owned(), show));
+    ACTIVE.store(true, Ordering::SeqCst);
+}
+
+/// Suppresses the dumps printed by [`binspect!`](crate::binspect),
+/// [`ebinspect!`](crate::ebinspect), [`binspect_ptr!`](crate::binspect_ptr),
+/// [`binspect_span!`](crate::binspect_span) and [`binspect_diff!`](crate::binspect_diff) for the
+/// types whose names match `pattern`, without changing the call sites.
+///
+/// The pattern is matched against the whole name of the type as given by
+/// [`type_name`](std::any::type_name), e.g. `alloc::vec::Vec<u8>`, where `*` matches any
+/// sequence of characters and `?` any single character. When the rules of `hide_types` and
+/// [`show_types`] overlap, the rule added last wins. Dumps written with
+/// [`write_binspect!`](crate::write_binspect) are never filtered.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{binspect, capture, hide_types, show_types};
+///
+/// hide_types("*Vec<*>");
+/// show_types("*Vec<u16>");
+/// let (bytes, words, n) = (vec![1u8], vec![2u16], 3u8);
+/// let out = capture(|| {
+///     binspect!(bytes);
+///     binspect!(words);
+///     binspect!(n);
+/// });
+/// assert!(!out.contains("= bytes\n"));
+/// assert!(out.contains(": alloc::vec::Vec<u16> = words\n"));
+/// assert!(out.contains(": u8 = n\n"));
+/// ```
+pub fn hide_types(pattern: &str) {
+    add(pattern, false)
+}
+
+/// Prints the dumps of the types whose names match `pattern` again, after [`hide_types`] hid
+/// them, e.g. to see a single type of a hidden module. The pattern is as for [`hide_types`].
+pub fn show_types(pattern: &str) {
+    add(pattern, true)
+}
+
+/// Removes the rules of [`hide_types`] and [`show_types`], so that all dumps are printed.
+pub fn clear_type_filters() {
+    lock().clear();
+    ACTIVE.store(false, Ordering::SeqCst);
+}
+
+/// Returns whether the dumps of `type_name` are printed.
+pub(crate) fn is_shown(type_name: &str) -> bool {
+    if !ACTIVE.load(Ordering::SeqCst) {
+        return true;
+    }
+    lock()
+        .iter()
+        .rev()
+        .find(|(pattern, _)| matches_glob(pattern, type_name))
+        .map_or(true, |&(_, show)| show)
+}
+
+/// Matches `s` against a pattern where `*` matches any sequence of characters and `?` any
+/// single character.
+fn matches_glob(pattern: &str, s: &str) -> bool {
+    let (p, s): (Vec<char>, Vec<char>) = (pattern.chars().collect(), s.chars().collect());
+    let (mut i, mut j) = (0, 0);
+    // The position after the last `*` in the pattern, and the position in `s` it matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while j < s.len() {
+        match p.get(i) {
+            Some('*') => {
+                i += 1;
+                star = Some((i, j));
+            }
+            Some(&c) if c == '?' || c == s[j] => {
+                i += 1;
+                j += 1;
+            }
+            _ => match star {
+                Some((after, matched)) => {
+                    i = after;
+                    j = matched + 1;
+                    star = Some((after, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[i..].iter().all(|&c| c == '*')
+}
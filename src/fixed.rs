@@ -0,0 +1,114 @@
+use std::fmt;
+
+/// A fixed-point or scaled integer format, whose value is the raw integer times
+/// `numerator / denominator`, for [`Lane::Fixed`](crate::Lane::Fixed) and
+/// [`Encoding::Fixed`](crate::schema::Encoding::Fixed).
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::schema::{Encoding, Endian, Schema};
+/// # use binspect::Fixed;
+/// let schema = Schema::new()
+///     .field("gain", 0, 4, Encoding::Fixed(Endian::Little, Fixed::q(16)))
+///     .field("temp", 4, 2, Encoding::Fixed(Endian::Little, Fixed::scaled(1, 100)));
+/// let mut buf: Vec<u8> = vec![];
+/// schema.write(&mut buf, b"\x00\x80\x01\x00\x2f\xf7").unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains("gain: q15.16le = 1.5\n"));
+/// assert!(out.ends_with("temp: i16le * 1/100 = -22.57\n"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fixed {
+    /// Whether the raw integer is in two's complement.
+    pub signed: bool,
+    pub numerator: i64,
+    pub denominator: u64,
+}
+
+impl Fixed {
+    /// Creates a signed Qm.n format with `frac_bits` fractional bits, e.g. `Fixed::q(15)` for Q15
+    /// samples or `Fixed::q(16)` for Q16.16.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frac_bits` is 64 or more, as no integer of up to 8 bytes has that many
+    /// fractional bits.
+    pub fn q(frac_bits: u32) -> Fixed {
+        assert!(
+            frac_bits < 64,
+            "{} fractional bits do not fit in 64 bits",
+            frac_bits
+        );
+        Fixed {
+            signed: true,
+            numerator: 1,
+            denominator: 1 << frac_bits,
+        }
+    }
+
+    /// Creates an unsigned UQm.n format with `frac_bits` fractional bits.
+    pub fn uq(frac_bits: u32) -> Fixed {
+        Fixed {
+            signed: false,
+            ..Fixed::q(frac_bits)
+        }
+    }
+
+    /// Creates a signed integer scaled by `numerator / denominator`, e.g. `Fixed::scaled(1, 100)`
+    /// for hundredths of a unit.
+    pub fn scaled(numerator: i64, denominator: u64) -> Fixed {
+        Fixed {
+            signed: true,
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Returns the number of fractional bits if this is a Q format.
+    fn frac_bits(&self) -> Option<u32> {
+        if self.numerator == 1 && self.denominator.is_power_of_two() {
+            Some(self.denominator.trailing_zeros())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value of the raw integer in the `len` lowest bytes of `raw`, `len` clamped to 1
+    /// to 8.
+    pub(crate) fn value(&self, raw: u64, len: usize) -> f64 {
+        let shift = 64 - 8 * len.max(1).min(8) as u32;
+        let raw = if self.signed {
+            ((raw << shift) as i64 >> shift) as f64
+        } else {
+            raw as f64
+        };
+        raw * self.numerator as f64 / self.denominator as f64
+    }
+
+    /// Writes the type of a `len`-byte integer in this format, e.g. `q15.16` or `i16 * 1/100`,
+    /// with `suffix` after the integer type.
+    pub(crate) fn write_type(
+        &self,
+        f: &mut fmt::Formatter,
+        len: usize,
+        suffix: &str,
+    ) -> fmt::Result {
+        let bits = 8 * len as u32;
+        let sign = if self.signed { 1 } else { 0 };
+        match self.frac_bits() {
+            Some(n) if n + sign <= bits => {
+                let q = if self.signed { "q" } else { "uq" };
+                write!(f, "{}{}.{}{}", q, bits - n - sign, n, suffix)
+            }
+            _ => {
+                let i = if self.signed { "i" } else { "u" };
+                write!(
+                    f,
+                    "{}{}{} * {}/{}",
+                    i, bits, suffix, self.numerator, self.denominator
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::io::{self, Write};
+
+use crate::as_bytes;
+use crate::diff::write_diff;
+use crate::schema::Schema;
+
+/// Layout rules of GPU buffers, for [`write_device_diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuLayout {
+    /// The rules of uniform buffers, where arrays and structs are aligned to 16 bytes.
+    Std140,
+    /// The rules of storage buffers, where arrays and structs are aligned as their elements.
+    Std430,
+}
+
+impl GpuLayout {
+    fn name(self) -> &'static str {
+        match self {
+            GpuLayout::Std140 => "std140",
+            GpuLayout::Std430 => "std430",
+        }
+    }
+
+    /// Returns the alignment of a field of `len` bytes. Fields of 4 and 8 bytes are taken as
+    /// scalars and `vec2`, of 12 and 16 bytes as `vec3` and `vec4`, and larger ones as arrays or
+    /// structs.
+    fn align(self, len: usize) -> usize {
+        match len {
+            0..=4 => 4,
+            5..=8 => 8,
+            9..=16 => 16,
+            _ if self == GpuLayout::Std140 || len % 16 == 0 => 16,
+            _ if len % 8 == 0 => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// Compares the fields of a host object with a buffer read back from a GPU or another
+/// accelerator, where the fields are expected at the offsets given by `layout`, and writes a line
+/// per field. Returns the number of fields which differ or are missing from the buffer.
+///
+/// A field whose offset on the host moves away from its offset in the buffer is followed by a hint
+/// on the padding, e.g. for a `vec3` which `std140` aligns to 16 bytes. The alignment of each
+/// field is derived from its size in `schema`, as made by [`schema_of!`](crate::schema_of), so
+/// the fields must be listed in their order in the buffer.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{schema_of, write_device_diff, GpuLayout};
+///
+/// #[repr(C)]
+/// struct Light {
+///     intensity: f32,
+///     color: [f32; 3],
+///     radius: f32,
+/// }
+///
+/// let light = Light { intensity: 1.0, color: [1.0, 0.5, 0.0], radius: 4.0 };
+/// // The buffer which a shader sees, with the color at offset 16.
+/// let mut device = [0u8; 32];
+/// device[..4].copy_from_slice(&1.0f32.to_ne_bytes());
+/// for (i, c) in light.color.iter().enumerate() {
+///     device[16 + i * 4..20 + i * 4].copy_from_slice(&c.to_ne_bytes());
+/// }
+/// device[28..].copy_from_slice(&4.0f32.to_ne_bytes());
+///
+/// let schema = schema_of!(light, intensity, color, radius);
+/// let mut buf: Vec<u8> = vec![];
+/// let differ = write_device_diff(&mut buf, &light, &schema, &device, GpuLayout::Std140).unwrap();
+/// assert_eq!(differ, 0);
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "intensity: host 0000..0004, std140 0000..0004, same\n\
+///      color: host 0004..0010, std140 0010..001c, same\n\
+///      \x20    + 12 bytes of padding expected before color\n\
+///      radius: host 0010..0014, std140 001c..0020, same\n"
+/// );
+/// ```
+pub fn write_device_diff<W: Write, T: ?Sized>(
+    mut w: W,
+    host: &T,
+    schema: &Schema,
+    device: &[u8],
+    layout: GpuLayout,
+) -> Result<usize, io::Error> {
+    let host = as_bytes(host);
+    let mut count = 0;
+    let mut end = 0;
+    let mut last_shift = 0;
+    for field in schema.fields() {
+        let align = layout.align(field.len);
+        let offset = (end + align - 1) / align * align;
+        end = offset + field.len;
+        write!(
+            w,
+            "{}: host {:04x}..{:04x}, {} {:04x}..{:04x}",
+            field.name,
+            field.offset,
+            field.end(),
+            layout.name(),
+            offset,
+            end
+        )?;
+        let expected = &host[field.offset.min(host.len())..field.end().min(host.len())];
+        match device.get(offset..end) {
+            None => {
+                writeln!(w, ", missing")?;
+                count += 1;
+            }
+            Some(actual) if actual == expected => writeln!(w, ", same")?,
+            Some(actual) => {
+                writeln!(w, ", differs")?;
+                write_diff(&mut w, expected, actual)?;
+                count += 1;
+            }
+        }
+        // The padding is reported where the distance between both offsets changes.
+        let shift = offset as isize - field.offset as isize;
+        if shift > last_shift {
+            let n = shift - last_shift;
+            writeln!(
+                w,
+                "     + {} bytes of padding expected before {}",
+                n, field.name
+            )?;
+        } else if shift < last_shift {
+            let n = last_shift - shift;
+            writeln!(
+                w,
+                "     + {} bytes of padding unexpected before {}",
+                n, field.name
+            )?;
+        }
+        last_shift = shift;
+    }
+    Ok(count)
+}
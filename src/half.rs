@@ -0,0 +1,26 @@
+//! Conversions of 16-bit floats, which have no primitive type.
+
+/// Converts an IEEE 754 half-precision float to `f32` exactly.
+pub(crate) fn f16_to_f32(bits: u16) -> f32 {
+    let sign = u32::from(bits & 0x8000) << 16;
+    let exponent = u32::from(bits >> 10 & 0x1f);
+    let mantissa = u32::from(bits & 0x3ff);
+    match exponent {
+        0 => {
+            // Zero or subnormal, `mantissa * 2^-24`.
+            let x = mantissa as f32 / (1 << 24) as f32;
+            if sign == 0 {
+                x
+            } else {
+                -x
+            }
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | mantissa << 13),
+        _ => f32::from_bits(sign | (exponent + 127 - 15) << 23 | mantissa << 13),
+    }
+}
+
+/// Converts a bfloat16, the upper half of an `f32`, to `f32`.
+pub(crate) fn bf16_to_f32(bits: u16) -> f32 {
+    f32::from_bits(u32::from(bits) << 16)
+}
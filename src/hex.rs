@@ -0,0 +1,260 @@
+//! Formatting of dumps into `fmt::Write`, which needs neither `std` nor a heap.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::json::ParseError;
+use crate::WIDTH;
+
+/// Writes the lines of the hex dump of `bytes` with their offsets to a `fmt::Write`, e.g. a
+/// `String` or a `fmt::Formatter`.
+///
+/// The lines are laid out as those of [`binspect!`](crate::binspect) with the default options.
+/// Unlike the macros, this function is available without the `std` feature. With it,
+/// [`hexdump`](crate::hexdump) writes the lines with any [`Options`](crate::Options) to
+/// `io::Write`.
+///
+/// # Examples
+///
+/// ```
+/// let mut s = String::new();
+/// binspect::write_hex(&mut s, b"ABCDEFGHIJKLMNOPQ").unwrap();
+/// assert_eq!(
+///     s,
+///     "0000 | 41 42 43 44 45 46 47 48 : 49 4a 4b 4c 4d 4e 4f 50\n0010 | 51\n"
+/// );
+/// ```
+pub fn write_hex<W: fmt::Write + ?Sized>(w: &mut W, bytes: &[u8]) -> fmt::Result {
+    for (r, row) in bytes.chunks(WIDTH).enumerate() {
+        write!(w, "{:04x} |", r * WIDTH)?;
+        for (j, x) in row.iter().enumerate() {
+            if j == WIDTH / 2 {
+                w.write_str(" :")?;
+            }
+            write!(w, " {:02x}", x)?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+/// Reads the bytes back from the lines of a hex dump, as written by [`write_hex`],
+/// [`hexdump`](crate::hexdump) or the macros with offsets, so that dumps saved from logs can be
+/// compared or replayed. Headers, notes and ASCII columns are skipped.
+///
+/// The text format guarantees, for any bytes and for the default options as well as with
+/// [`Options::ascii`](crate::Options::ascii):
+///
+/// - `parse_hex` returns the bytes which were dumped;
+/// - lines hold at most 16 bytes and are at most 56 characters long without the ASCII column, or
+///   74 with it;
+/// - rendering does not panic.
+///
+/// These hold for [`Header::Verbose`](crate::Header::Verbose) and for headers decorated with
+/// [`Options::location`](crate::Options::location),
+/// [`thread`](crate::Options::thread), [`timestamps`](crate::Options::timestamps) and
+/// [`align_headers`](crate::Options::align_headers) as well, and rendering does not panic with
+/// any other options either. Property tests check this over random bytes and options.
+///
+/// # Errors
+///
+/// Fails if a line is not a line of a dump, or if the offsets of the lines are not contiguous,
+/// e.g. in collapsed dumps.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{hexdump, parse_hex, write_hex, Options};
+///
+/// let ascii = Options {
+///     ascii: true,
+///     ..Options::default()
+/// };
+/// for len in 0..=48 {
+///     for seed in 0..8u32 {
+///         let bytes: Vec<u8> = (0..len as u32)
+///             .map(|i| (i.wrapping_mul(seed * 73 + 11) >> (seed % 5)) as u8)
+///             .collect();
+///         let mut s = String::new();
+///         write_hex(&mut s, &bytes).unwrap();
+///         assert_eq!(parse_hex(&s).unwrap(), bytes);
+///         assert!(s.lines().all(|line| line.chars().count() <= 56));
+///
+///         let mut buf: Vec<u8> = vec![];
+///         hexdump(&mut buf, &bytes, &ascii).unwrap();
+///         let s = String::from_utf8(buf).unwrap();
+///         assert_eq!(parse_hex(&s).unwrap(), bytes);
+///         assert!(s.lines().all(|line| line.chars().count() <= 74));
+///     }
+/// }
+///
+/// let mut buf: Vec<u8> = vec![];
+/// binspect::write_binspect!(&mut buf, *b"GET /").unwrap();
+/// assert_eq!(parse_hex(&String::from_utf8(buf).unwrap()).unwrap(), b"GET /");
+/// assert!(parse_hex("0000 | 01 02\n0020 | 03\n").is_err());
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_hex(dump: &str) -> Result<Vec<u8>, ParseError> {
+    let mut bytes: Vec<u8> = vec![];
+    let mut start = None;
+    for (n, line) in dump.lines().enumerate() {
+        let error = |message: &str| ParseError::new(format!("line {}: {}", n + 1, message));
+        if line.starts_with("-----+") || line.starts_with("     ") || line.trim().is_empty() {
+            continue;
+        }
+        let bar = line
+            .find(" |")
+            .ok_or_else(|| error("not a line of a dump"))?;
+        let offset =
+            usize::from_str_radix(&line[..bar], 16).map_err(|_| error("invalid offset"))?;
+        let start = *start.get_or_insert(offset);
+        if offset != start + bytes.len() {
+            return Err(error("offsets are not contiguous"));
+        }
+        let row = bytes.len();
+        let mut pos = bar + 2;
+        while bytes.len() - row < WIDTH {
+            let cell = match line.get(pos..pos + 3) {
+                Some(cell) if cell.starts_with(' ') => &cell[1..],
+                _ => break,
+            };
+            if cell.starts_with(':') {
+                pos += 2;
+                continue;
+            }
+            match u8::from_str_radix(cell, 16) {
+                Ok(x) if cell.chars().all(|c| c.is_ascii_hexdigit()) => bytes.push(x),
+                _ => break,
+            }
+            pos += 3;
+        }
+    }
+    Ok(bytes)
+}
+
+#[doc(hidden)]
+pub fn write_fmt_internal<W: fmt::Write + ?Sized>(
+    w: &mut W,
+    address: *const u8,
+    type_name: &str,
+    source: &str,
+    bytes: &[u8],
+) -> fmt::Result {
+    writeln!(w, "-----+ {:p}: {} = {}", address, type_name, source)?;
+    write_hex(w, bytes)
+}
+
+/// The header and the lines of a dump without the newline at its end, for loggers which add their
+/// own, e.g. [`log_binspect!`](crate::log_binspect) without the `std` feature.
+#[doc(hidden)]
+pub struct FmtDump<'a> {
+    pub address: *const u8,
+    pub type_name: &'a str,
+    pub source: &'a str,
+    pub bytes: &'a [u8],
+}
+
+impl fmt::Display for FmtDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut w = Trimmed {
+            w: f,
+            pending: false,
+        };
+        write_fmt_internal(
+            &mut w,
+            self.address,
+            self.type_name,
+            self.source,
+            self.bytes,
+        )
+    }
+}
+
+/// Holds back a trailing newline until more output follows.
+struct Trimmed<'a, W: fmt::Write + ?Sized> {
+    w: &'a mut W,
+    pending: bool,
+}
+
+impl<W: fmt::Write + ?Sized> fmt::Write for Trimmed<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            return Ok(());
+        }
+        if self.pending {
+            self.w.write_char('\n')?;
+        }
+        self.pending = s.ends_with('\n');
+        let n = if self.pending { s.len() - 1 } else { s.len() };
+        self.w.write_str(&s[..n])
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub fn format_internal(address: *const u8, type_name: &str, source: &str, bytes: &[u8]) -> String {
+    let mut s = String::new();
+    write_fmt_internal(&mut s, address, type_name, source, bytes).unwrap();
+    s
+}
+
+/// Returns the memory address and the hex representation of an object as a `String`, as printed
+/// by [`binspect!`](crate::binspect) with the default options.
+///
+/// Requires the `alloc` feature only, so that it works in `#![no_std]` crates with a global
+/// allocator.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::format_binspect;
+///
+/// let v = [1u16, 2];
+/// let s = format_binspect!(v);
+/// assert!(s.starts_with("-----+ 0x"));
+/// assert!(s.ends_with(": [u16; 2] = v\n0000 | 01 00 02 00\n"));
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! format_binspect {
+    ($v: expr) => {
+        match &$v {
+            t => $crate::format_internal(
+                t as *const _ as *const u8,
+                $crate::type_name_of(t),
+                stringify!($v),
+                $crate::as_bytes(t),
+            ),
+        }
+    };
+}
+
+/// Writes the memory address and the hex representation of an object to `fmt::Write`.
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! write_binspect_fmt {
+    ($w: expr, $v: expr) => {
+        match &$v {
+            t => $crate::write_fmt_internal(
+                $w,
+                t as *const _ as *const u8,
+                $crate::type_name_of(t),
+                stringify!($v),
+                $crate::as_bytes(t),
+            ),
+        }
+    };
+    ($w: expr, $v: expr, $len: expr) => {
+        match &$v {
+            t => $crate::write_fmt_internal(
+                $w,
+                t as *const _ as *const u8,
+                $crate::type_name_of(t),
+                stringify!($v),
+                $crate::as_bytes_with_len(t, $len),
+            ),
+        }
+    };
+}
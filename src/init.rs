@@ -0,0 +1,131 @@
+use std::env;
+use std::fs::OpenOptions;
+use std::sync::Once;
+
+use crate::{dump_kept_on_panic, hide_types, limit, max_output, set_sink, show_types};
+
+/// Sets up the process-wide behavior of this crate in one call, e.g. at the start of `main`.
+///
+/// Installs the panic hook of [`dump_kept_on_panic`], so that the values kept with
+/// [`binspect_keep!`](crate::binspect_keep) are dumped to stderr when a thread panics. Calling
+/// this again does nothing.
+///
+/// The environment is left alone, so that a sink or type filters set for one program do not
+/// leak into the libraries and tests it runs. [`init_from_env`] reads the environment as well.
+///
+/// # Examples
+///
+/// ```
+/// binspect::init();
+/// ```
+pub fn init() {
+    dump_kept_on_panic();
+}
+
+/// Sets up this crate like [`init`], and reads its environment variables right away instead of
+/// at the first dump, reporting invalid values on stderr. Calling this again does nothing, so
+/// that the sink is not reopened and the type filters are not added twice.
+///
+/// The variables are:
+///
+/// - `BINSPECT_MAX_OUTPUT`: the limit of [`set_max_output`](crate::set_max_output), e.g. `10MB`.
+/// - `BINSPECT_FULL`: prints collapsed dumps in full, see
+///   [`Options::collapsed`](crate::Options::collapsed).
+/// - `BINSPECT_BASELINE_DIR`, `BINSPECT_UPDATE_BASELINE` and `BINSPECT_BIT_DIFF`: see
+///   [`binspect_baseline!`](crate::binspect_baseline).
+/// - `BINSPECT_FORMAT`: `text`, `json` or `yaml`, the format of dumps printed in the text format,
+///   see [`Options::format`](crate::Options::format).
+/// - `BINSPECT_COLOR`: `auto`, `always` or `never`, overriding
+///   [`ColorChoice::Auto`](crate::ColorChoice::Auto) (`color` feature).
+/// - `NO_COLOR`: disables colors chosen automatically (`color` feature).
+/// - `BINSPECT_SINK`: a file to which the dumps printed to stdout and stderr are appended instead,
+///   see [`set_sink`](crate::set_sink). The file is created if needed.
+/// - `BINSPECT_HIDE` and `BINSPECT_SHOW`: patterns of [`hide_types`](crate::hide_types) and
+///   [`show_types`](crate::show_types) separated by `|`, since type names may contain commas and
+///   semicolons, e.g. `*Vec<*>|core::*`. The patterns of `BINSPECT_SHOW` are added last, so they
+///   win over those of `BINSPECT_HIDE`.
+///
+/// Unlike these, the options of [`register_options`](crate::register_options) are Rust values,
+/// e.g. closures for comments, which have no text form, so they are only registered in code.
+///
+/// # Examples
+///
+/// ```
+/// use std::{env, fs};
+///
+/// use binspect::{binspect, capture};
+///
+/// let log = env::temp_dir().join(format!("binspect-init-{}.log", std::process::id()));
+/// env::set_var("BINSPECT_SINK", &log);
+/// env::set_var("BINSPECT_HIDE", "alloc::*|*Vec<*>");
+/// env::set_var("BINSPECT_SHOW", "*Vec<u16>");
+/// binspect::init_from_env();
+///
+/// let (s, bytes, words) = (String::from("A"), vec![1u8], vec![2u16]);
+/// let out = capture(|| {
+///     binspect!(s);
+///     binspect!(bytes);
+///     binspect!(words);
+/// });
+/// assert!(!out.contains("= s\n") && !out.contains("= bytes\n"));
+/// assert!(out.contains("= words\n"));
+///
+/// binspect!(words);
+/// assert!(fs::read_to_string(&log).unwrap().contains("= words\n"));
+/// fs::remove_file(&log).unwrap();
+///
+/// // The variables were read by the first call.
+/// env::set_var("BINSPECT_HIDE", "*");
+/// binspect::init_from_env();
+/// env::set_var("BINSPECT_FORMAT", "json");
+/// let out = capture(|| binspect!(words));
+/// assert!(out.starts_with("{\"addr\":") && out.contains("\"src\":\"words\""), "{}", out);
+/// ```
+pub fn init_from_env() {
+    static INIT: Once = Once::new();
+    INIT.call_once(read_env);
+}
+
+fn read_env() {
+    init();
+    if let Some(value) = env::var_os("BINSPECT_MAX_OUTPUT") {
+        if value.to_str().and_then(limit::parse_size).is_none() {
+            eprintln!("binspect: ignoring invalid BINSPECT_MAX_OUTPUT {:?}", value);
+        }
+    }
+    max_output();
+    if let Some(value) = env::var_os("BINSPECT_FORMAT") {
+        if value.to_str().and_then(crate::dump::parse_format).is_none() {
+            eprintln!("binspect: ignoring invalid BINSPECT_FORMAT {:?}", value);
+        }
+    }
+    #[cfg(feature = "color")]
+    {
+        if let Some(value) = env::var_os("BINSPECT_COLOR") {
+            if value
+                .to_str()
+                .and_then(crate::color::parse_choice)
+                .is_none()
+            {
+                eprintln!("binspect: ignoring invalid BINSPECT_COLOR {:?}", value);
+            }
+        }
+    }
+    if let Some(path) = env::var_os("BINSPECT_SINK") {
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => set_sink(Box::new(file)),
+            Err(e) => eprintln!("binspect: ignoring BINSPECT_SINK {:?}: {}", path, e),
+        }
+    }
+    for &(name, show) in &[("BINSPECT_HIDE", false), ("BINSPECT_SHOW", true)] {
+        if let Ok(patterns) = env::var(name) {
+            for pattern in patterns.split('|').filter(|p| !p.is_empty()) {
+                if show {
+                    show_types(pattern);
+                } else {
+                    hide_types(pattern);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! A minimal JSON reader and writer for the structured formats of this crate.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::address::Address;
+use crate::{hidden, is_masked, is_unreadable, region, DumpInfo, Options};
+
+/// The version of the structured formats written by this crate: JSON and YAML dumps and layout
+/// fingerprints.
+///
+/// Outputs carry it as `version`. It is raised only on changes which break readers, such as
+/// removed keys or keys whose meaning changes. New keys can be added within a version, so readers
+/// should ignore the keys they do not know.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Returns the version of a JSON document written by this crate, or an error if it is newer than
+/// [`FORMAT_VERSION`] and so may not be read correctly.
+///
+/// Documents without `version`, written before it was added, are of version 1. Readers of this
+/// crate, such as [`Merge`](crate::Merge), check the version in the same way.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{check_format_version, write_binspect_json, FORMAT_VERSION};
+///
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_json!(&mut buf, 1u8).unwrap();
+/// let json = String::from_utf8(buf).unwrap();
+/// assert_eq!(check_format_version(&json), Ok(FORMAT_VERSION));
+/// assert_eq!(check_format_version(r#"{"toolchain": "stable", "layouts": []}"#), Ok(1));
+/// assert!(check_format_version(r#"{"version": 99}"#).is_err());
+/// assert!(check_format_version(r#"{"version": 4294967297}"#).is_err());
+/// ```
+pub fn check_format_version(s: &str) -> Result<u32, ParseError> {
+    check_version(&parse(s)?)
+}
+
+/// Returns the version of a parsed document like [`check_format_version`].
+pub(crate) fn check_version(value: &Value) -> Result<u32, ParseError> {
+    let version = match value.get("version") {
+        None => 1,
+        Some(v) => v
+            .as_usize()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| ParseError::new("invalid \"version\""))?,
+    };
+    if version > FORMAT_VERSION {
+        return Err(ParseError::new(format!(
+            "unsupported format version {} (supported up to {})",
+            version, FORMAT_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+/// Writes a double-quoted string, which is valid in both YAML and JSON.
+pub(crate) fn write_quoted<W: Write>(w: &mut W, s: &str) -> Result<(), io::Error> {
+    write!(w, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(w, "\\\"")?,
+            '\\' => write!(w, "\\\\")?,
+            '\n' => write!(w, "\\n")?,
+            '\r' => write!(w, "\\r")?,
+            '\t' => write!(w, "\\t")?,
+            c if c.is_control() => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{}", c)?,
+        }
+    }
+    write!(w, "\"")
+}
+
+/// Writes a dump as a JSON object on a single line.
+pub(crate) fn write_json<W: Write>(
+    mut w: W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+) -> Result<(), io::Error> {
+    let address = Address(info.address, options);
+    write!(w, "{{\"addr\":")?;
+    write_quoted(&mut w, &address.to_string())?;
+    if options.regions {
+        if let Some(region) = region::find(address.0 as usize) {
+            write!(w, ",\"region\":")?;
+            write_quoted(&mut w, &region)?;
+        }
+    }
+    write!(w, ",\"type\":")?;
+    write_quoted(&mut w, info.type_name)?;
+    write!(w, ",\"size\":{},\"src\":", info.size)?;
+    write_quoted(&mut w, info.source)?;
+    if let Some(label) = info.label {
+        write!(w, ",\"label\":")?;
+        write_quoted(&mut w, label)?;
+    }
+    if !info.meta.is_empty() {
+        write!(w, ",\"meta\":{{")?;
+        for (i, (key, value)) in info.meta.iter().enumerate() {
+            if i > 0 {
+                write!(w, ",")?;
+            }
+            write_quoted(&mut w, key)?;
+            write!(w, ":")?;
+            write_quoted(&mut w, &value.to_string())?;
+        }
+        write!(w, "}}")?;
+    }
+    write!(w, ",\"file\":")?;
+    write_quoted(&mut w, info.file)?;
+    write!(w, ",\"line\":{},\"column\":{}", info.line, info.column)?;
+    if options.timestamps {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        write!(w, ",\"time\":{}", time.as_micros())?;
+    }
+    write!(w, ",\"version\":{},\"bytes\":[", FORMAT_VERSION)?;
+    for (i, x) in bytes.iter().enumerate() {
+        if i > 0 {
+            write!(w, ",")?;
+        }
+        if is_unreadable(unreadable, i)
+            || hidden(info.schema, i).is_some()
+            || options.placeholders && is_masked(bytes, i)
+        {
+            write!(w, "null")?;
+        } else {
+            let x = match &options.mask {
+                Some(mask) => mask.apply(i, *x),
+                None => *x,
+            };
+            write!(w, "{}", x)?;
+        }
+    }
+    writeln!(w, "]}}")
+}
+
+/// An error while reading a structured format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new<S: Into<String>>(message: S) -> ParseError {
+        ParseError {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Value::Number(n) if n >= 0.0 && n.fract() == 0.0 => Some(n as usize),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a required key, or an error naming it.
+    pub(crate) fn field<'a, T, F>(&'a self, key: &str, f: F) -> Result<T, ParseError>
+    where
+        F: FnOnce(&'a Value) -> Option<T>,
+    {
+        self.get(key)
+            .and_then(f)
+            .ok_or_else(|| ParseError::new(format!("missing or invalid {:?}", key)))
+    }
+}
+
+pub(crate) fn parse(s: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser {
+        chars: s.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(format!("{} at character {}", message, self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected {:?}", c)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, ParseError> {
+        for c in word.chars() {
+            if self.peek() != Some(c) {
+                return Err(self.error("invalid literal"));
+            }
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Value::Null),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut values = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array(values));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Value::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    entries.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some('}') => {
+                            self.pos += 1;
+                            return Ok(Value::Object(entries));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                let s: String = self.chars[start..self.pos].iter().collect();
+                s.parse()
+                    .map(Value::Number)
+                    .map_err(|_| self.error("invalid number"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        if self.peek() != Some('"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let c = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match c {
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16)
+                                .map_err(|_| self.error("invalid escape"))?;
+                            self.pos += 4;
+                            s.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        c => s.push(c),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::any::type_name;
+use std::io::{self, Write};
+use std::panic;
+use std::sync::{Mutex, Once};
+
+use crate::{as_bytes, DumpInfo, Options, Renderer};
+
+/// A value leaked by [`keep`].
+struct Kept {
+    address: usize,
+    bytes: Vec<u8>,
+    type_name: &'static str,
+    source: &'static str,
+    file: &'static str,
+    line: u32,
+    column: u32,
+}
+
+global! {
+    fn kept() -> &'static Mutex<Vec<Kept>> = Mutex::new(vec![]);
+}
+
+/// Leaks `value` and registers it, so that it can be dumped again at shutdown with
+/// [`print_kept`] or when a thread panics with [`dump_kept_on_panic`].
+///
+/// [`binspect_keep!`] does the same and shows the expression in the dumps.
+///
+/// The bytes of the value are copied when it is kept, so later dumps do not read memory which may
+/// change through interior mutability, e.g. of atomics, and show the value as it was kept.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{keep, write_kept};
+/// let config: &'static [u16; 2] = keep([80, 443]);
+/// assert_eq!(config[1], 443);
+///
+/// let mut buf: Vec<u8> = vec![];
+/// write_kept(&mut buf).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().contains(": [u16; 2] = keep(..)\n0000 | 50 00 bb 01\n"));
+///
+/// use std::sync::atomic::{AtomicU32, Ordering};
+///
+/// let counter = keep(AtomicU32::new(0x0f0f_0f0f));
+/// counter.store(2, Ordering::SeqCst);
+/// let mut buf: Vec<u8> = vec![];
+/// write_kept(&mut buf).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().contains("AtomicU32 = keep(..)\n0000 | 0f 0f 0f 0f\n"));
+/// ```
+pub fn keep<T: Sync + 'static>(value: T) -> &'static T {
+    keep_internal(value, "keep(..)", "", 0, 0)
+}
+
+#[doc(hidden)]
+pub fn keep_internal<T: Sync + 'static>(
+    value: T,
+    source: &'static str,
+    file: &'static str,
+    line: u32,
+    column: u32,
+) -> &'static T {
+    let value: &'static T = Box::leak(Box::new(value));
+    let mut kept = kept().lock().unwrap_or_else(|e| e.into_inner());
+    kept.push(Kept {
+        address: value as *const T as usize,
+        bytes: as_bytes(value).to_vec(),
+        type_name: type_name::<T>(),
+        source,
+        file,
+        line,
+        column,
+    });
+    value
+}
+
+/// Writes the dumps of the values leaked by [`keep`] and [`binspect_keep!`] so far, in the order
+/// they were kept.
+pub fn write_kept<W: Write>(mut w: W) -> Result<(), io::Error> {
+    let kept = kept().lock().unwrap_or_else(|e| e.into_inner());
+    let options = Options::default();
+    for k in kept.iter() {
+        let info = DumpInfo {
+            address: k.address as *const u8,
+            type_name: k.type_name,
+            size: k.bytes.len(),
+            source: k.source,
+            label: None,
+            file: k.file,
+            line: k.line,
+            column: k.column,
+            schema: None,
+            meta: &[],
+        };
+        options.render(&mut w, &info, &k.bytes)?;
+    }
+    Ok(())
+}
+
+/// Prints the dumps of the values leaked by [`keep`] and [`binspect_keep!`] to stdout, e.g. at
+/// the end of `main`.
+pub fn print_kept() {
+    crate::limit::limited(io::stdout(), |w| write_kept(w)).unwrap()
+}
+
+/// Installs a panic hook which prints the dumps of the kept values to stderr after the message
+/// of the previous hook. Calling this again does nothing.
+pub fn dump_kept_on_panic() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            let _ = write_kept(io::stderr().lock());
+        }));
+    });
+}
+
+/// Leaks a value with [`keep`] and returns a `&'static` reference to it, showing the expression
+/// in later dumps of the kept values.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{binspect_keep, dump_kept_on_panic, write_kept};
+/// dump_kept_on_panic();
+/// let state = binspect_keep!((7u8, 'x'));
+/// assert_eq!(state.0, 7);
+///
+/// let mut buf: Vec<u8> = vec![];
+/// write_kept(&mut buf).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().contains(": (u8, char) = (7u8, 'x')\n"));
+/// ```
+#[macro_export]
+macro_rules! binspect_keep {
+    ($v: expr) => {
+        $crate::keep_internal($v, stringify!($v), file!(), line!(), column!())
+    };
+}
@@ -0,0 +1,112 @@
+use std::io::{self, Write};
+
+use crate::Fixed;
+
+use crate::half;
+
+/// A type of the lanes of SIMD vectors, e.g. [`F32`](Lane::F32) for `__m128` or `Simd<f32, 4>`,
+/// for [`Options::lanes`](crate::Options::lanes).
+///
+/// Lanes are read in the native byte order, lane 0 at the lowest address, as `_mm_setr_*` and
+/// `Simd::from_array` lay them out.
+///
+/// # Examples
+///
+/// 16-bit floats of ML weights and vertex buffers can be decoded:
+///
+/// ```
+/// # use binspect::{write_binspect_with, Lane, Options};
+/// let options = Options {
+///     lanes: Some(Lane::F16),
+///     ..Options::default()
+/// };
+/// let weights: [u16; 4] = [0x3c00, 0xc000, 0x3555, 0x7c00];
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, weights).unwrap();
+/// assert!(String::from_utf8(buf)
+///     .unwrap()
+///     .ends_with("  lanes 0-3: 1.0, -2.0, 0.33325195, inf\n"));
+/// ```
+///
+/// Sizes of fixed-point lanes out of range are clamped:
+///
+/// ```
+/// # use binspect::{write_binspect_with, Fixed, Lane, Options};
+/// for &size in &[0, 1, 8, 9, usize::max_value()] {
+///     let options = Options {
+///         lanes: Some(Lane::Fixed(size, Fixed::q(4))),
+///         ..Options::default()
+///     };
+///     let mut buf: Vec<u8> = vec![];
+///     write_binspect_with!(&mut buf, &options, [0x10u8; 8]).unwrap();
+///     if size <= 1 {
+///         assert!(String::from_utf8(buf).unwrap().contains(" lanes 0-7: 1.0, 1.0, "));
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lane {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    /// IEEE 754 half-precision floats.
+    F16,
+    /// bfloat16, the upper halves of `f32`.
+    BF16,
+    F32,
+    F64,
+    /// Fixed-point or scaled integers of the given size in bytes, from 1 to 8, e.g.
+    /// `Lane::Fixed(2, Fixed::q(15))` for Q15 samples. Other sizes are clamped to this range.
+    Fixed(usize, Fixed),
+}
+
+impl Lane {
+    /// Returns the size of a lane in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Lane::U8 | Lane::I8 => 1,
+            Lane::U16 | Lane::I16 | Lane::F16 | Lane::BF16 => 2,
+            Lane::U32 | Lane::I32 | Lane::F32 => 4,
+            Lane::U64 | Lane::I64 | Lane::F64 => 8,
+            Lane::Fixed(size, _) => size.max(1).min(8),
+        }
+    }
+
+    /// Writes the value of a lane of `self.size()` bytes.
+    pub(crate) fn write<W: Write>(self, w: &mut W, bytes: &[u8]) -> Result<(), io::Error> {
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        let mut b2 = [0u8; 2];
+        let mut b4 = [0u8; 4];
+        b2.copy_from_slice(&buf[..2]);
+        b4.copy_from_slice(&buf[..4]);
+        match self {
+            Lane::U8 => write!(w, "{}", buf[0]),
+            Lane::I8 => write!(w, "{}", buf[0] as i8),
+            Lane::U16 => write!(w, "{}", u16::from_ne_bytes(b2)),
+            Lane::I16 => write!(w, "{}", i16::from_ne_bytes(b2)),
+            Lane::U32 => write!(w, "{}", u32::from_ne_bytes(b4)),
+            Lane::I32 => write!(w, "{}", i32::from_ne_bytes(b4)),
+            Lane::U64 => write!(w, "{}", u64::from_ne_bytes(buf)),
+            Lane::I64 => write!(w, "{}", i64::from_ne_bytes(buf)),
+            Lane::F16 => write!(w, "{:?}", half::f16_to_f32(u16::from_ne_bytes(b2))),
+            Lane::BF16 => write!(w, "{:?}", half::bf16_to_f32(u16::from_ne_bytes(b2))),
+            Lane::F32 => write!(w, "{:?}", f32::from_bits(u32::from_ne_bytes(b4))),
+            Lane::F64 => write!(w, "{:?}", f64::from_bits(u64::from_ne_bytes(buf))),
+            Lane::Fixed(_, fixed) => {
+                let fold = |acc: u64, &b: &u8| acc << 8 | u64::from(b);
+                let raw = if cfg!(target_endian = "little") {
+                    bytes.iter().rev().fold(0, fold)
+                } else {
+                    bytes.iter().fold(0, fold)
+                };
+                write!(w, "{:?}", fixed.value(raw, bytes.len()))
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! Layout fingerprints of types, to compare layouts across toolchains.
+//!
+//! # Examples
+//!
+//! ```
+//! use binspect::layout::{compare, Fingerprints, Layout};
+//!
+//! #[allow(dead_code)]
+//! struct Header {
+//!     tag: u8,
+//!     len: u32,
+//! }
+//!
+//! let mut stable = Fingerprints::new("stable");
+//! stable.push(Layout::of::<Header>().field("tag", 4, 1).field("len", 0, 4));
+//! let json = stable.to_json();
+//!
+//! // ... later, in a build with another toolchain
+//! let mut nightly = Fingerprints::new("nightly");
+//! nightly.push(Layout::of::<Header>().field("tag", 0, 1).field("len", 4, 4));
+//!
+//! let stable = Fingerprints::from_json(&json).unwrap();
+//! let mut buf: Vec<u8> = vec![];
+//! compare(&mut buf, &stable, &nightly).unwrap();
+//! assert!(String::from_utf8(buf).unwrap().ends_with(
+//!     "  field tag: offset 4 -> 0\n  field len: offset 0 -> 4\n"
+//! ));
+//! ```
+
+use std::any::type_name;
+use std::io::{self, Write};
+use std::mem;
+
+use crate::json::{self, write_quoted, Value, FORMAT_VERSION};
+
+pub use crate::json::ParseError;
+
+/// The layout of a field of a type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// The layout of a type: its size, its alignment and optionally the layout of its fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub name: String,
+    pub size: usize,
+    pub align: usize,
+    pub fields: Vec<FieldLayout>,
+}
+
+impl Layout {
+    /// Returns the layout of `T` without fields.
+    pub fn of<T>() -> Layout {
+        Layout {
+            name: type_name::<T>().to_owned(),
+            size: mem::size_of::<T>(),
+            align: mem::align_of::<T>(),
+            fields: vec![],
+        }
+    }
+
+    /// Adds a field, e.g. with an offset given by `core::mem::offset_of!`.
+    pub fn field(mut self, name: &str, offset: usize, size: usize) -> Layout {
+        self.fields.push(FieldLayout {
+            name: name.to_owned(),
+            offset,
+            size,
+        });
+        self
+    }
+}
+
+/// Layouts of types observed with a toolchain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fingerprints {
+    /// A name of the toolchain or the build, e.g. `stable` or `1.75.0`.
+    pub toolchain: String,
+    pub layouts: Vec<Layout>,
+}
+
+impl Fingerprints {
+    /// Creates empty fingerprints for a toolchain.
+    pub fn new(toolchain: &str) -> Fingerprints {
+        Fingerprints {
+            toolchain: toolchain.to_owned(),
+            layouts: vec![],
+        }
+    }
+
+    /// Adds a layout.
+    pub fn push(&mut self, layout: Layout) {
+        self.layouts.push(layout);
+    }
+
+    /// Writes the fingerprints as JSON, one layout per line.
+    pub fn write_json<W: Write>(&self, mut w: W) -> Result<(), io::Error> {
+        write!(w, "{{\"version\": {}, \"toolchain\": ", FORMAT_VERSION)?;
+        write_quoted(&mut w, &self.toolchain)?;
+        write!(w, ", \"layouts\": [")?;
+        for (i, layout) in self.layouts.iter().enumerate() {
+            write!(w, "{}\n  {{\"name\": ", if i == 0 { "" } else { "," })?;
+            write_quoted(&mut w, &layout.name)?;
+            write!(
+                w,
+                ", \"size\": {}, \"align\": {}, \"fields\": [",
+                layout.size, layout.align
+            )?;
+            for (j, field) in layout.fields.iter().enumerate() {
+                write!(w, "{}{{\"name\": ", if j == 0 { "" } else { ", " })?;
+                write_quoted(&mut w, &field.name)?;
+                write!(
+                    w,
+                    ", \"offset\": {}, \"size\": {}}}",
+                    field.offset, field.size
+                )?;
+            }
+            write!(w, "]}}")?;
+        }
+        writeln!(w, "\n]}}")
+    }
+
+    /// Returns the fingerprints as JSON.
+    pub fn to_json(&self) -> String {
+        let mut buf: Vec<u8> = vec![];
+        self.write_json(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    /// Reads fingerprints written by [`Fingerprints::write_json`].
+    pub fn from_json(s: &str) -> Result<Fingerprints, ParseError> {
+        let value = json::parse(s)?;
+        json::check_version(&value)?;
+        let mut fingerprints = Fingerprints::new(value.field("toolchain", Value::as_str)?);
+        for layout in value.field("layouts", Value::as_array)? {
+            let mut fields = vec![];
+            for field in layout.field("fields", Value::as_array)? {
+                fields.push(FieldLayout {
+                    name: field.field("name", Value::as_str)?.to_owned(),
+                    offset: field.field("offset", Value::as_usize)?,
+                    size: field.field("size", Value::as_usize)?,
+                });
+            }
+            fingerprints.push(Layout {
+                name: layout.field("name", Value::as_str)?.to_owned(),
+                size: layout.field("size", Value::as_usize)?,
+                align: layout.field("align", Value::as_usize)?,
+                fields,
+            });
+        }
+        Ok(fingerprints)
+    }
+}
+
+/// Writes a report of the types whose size, alignment or field layout differ between `old` and
+/// `new`. Returns the number of types reported.
+///
+/// ```text
+/// --- stable
+/// +++ nightly
+/// my_crate::Header
+///   field tag: offset 4 -> 0
+///   field len: offset 0 -> 4
+/// my_crate::Footer: only in stable
+/// ```
+pub fn compare<W: Write>(
+    mut w: W,
+    old: &Fingerprints,
+    new: &Fingerprints,
+) -> Result<usize, io::Error> {
+    writeln!(w, "--- {}\n+++ {}", old.toolchain, new.toolchain)?;
+    let mut count = 0;
+    for a in &old.layouts {
+        let b = match new.layouts.iter().find(|b| b.name == a.name) {
+            Some(b) => b,
+            None => {
+                writeln!(w, "{}: only in {}", a.name, old.toolchain)?;
+                count += 1;
+                continue;
+            }
+        };
+        let mut changes = vec![];
+        if a.size != b.size {
+            changes.push(format!("size {} -> {}", a.size, b.size));
+        }
+        if a.align != b.align {
+            changes.push(format!("align {} -> {}", a.align, b.align));
+        }
+        for fa in &a.fields {
+            match b.fields.iter().find(|fb| fb.name == fa.name) {
+                Some(fb) if fa.offset != fb.offset => changes.push(format!(
+                    "field {}: offset {} -> {}",
+                    fa.name, fa.offset, fb.offset
+                )),
+                Some(fb) if fa.size != fb.size => changes.push(format!(
+                    "field {}: size {} -> {}",
+                    fa.name, fa.size, fb.size
+                )),
+                Some(_) => {}
+                None => changes.push(format!("field {}: only in {}", fa.name, old.toolchain)),
+            }
+        }
+        for fb in &b.fields {
+            if !a.fields.iter().any(|fa| fa.name == fb.name) {
+                changes.push(format!("field {}: only in {}", fb.name, new.toolchain));
+            }
+        }
+        if !changes.is_empty() {
+            writeln!(w, "{}", a.name)?;
+            for change in changes {
+                writeln!(w, "  {}", change)?;
+            }
+            count += 1;
+        }
+    }
+    for b in &new.layouts {
+        if !old.layouts.iter().any(|a| a.name == b.name) {
+            writeln!(w, "{}: only in {}", b.name, new.toolchain)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Asserts the size and the alignment of a type, e.g. in a test catching accidental layout changes.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::assert_layout;
+/// #[repr(C)]
+/// struct Header {
+///     tag: u8,
+///     len: u32,
+/// }
+///
+/// assert_layout!(Header, size = 8, align = 4);
+/// ```
+///
+/// ```should_panic
+/// # use binspect::assert_layout;
+/// assert_layout!(u64, size = 4, align = 4);
+/// ```
+#[macro_export]
+macro_rules! assert_layout {
+    ($t: ty, size = $size: expr, align = $align: expr $(,)?) => {{
+        let layout = $crate::layout::Layout::of::<$t>();
+        assert!(
+            layout.size == $size && layout.align == $align,
+            "layout of {} changed: expected size {} and align {}, found size {} and align {}",
+            layout.name,
+            $size,
+            $align,
+            layout.size,
+            layout.align
+        );
+    }};
+}
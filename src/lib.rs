@@ -7,11 +7,14 @@ Please read [Data Layout - The Rustonomicon](https://doc.rust-lang.org/stable/no
 # Examples
 
 ```rust
+# #[cfg(feature = "std")]
+# {
 use binspect::binspect;
 
 let s = "ABC";
 binspect!(s);
 binspect!(*s);
+# }
 ```
 
 An example of output (depends on compilation and runtime environments):
@@ -22,12 +25,285 @@ An example of output (depends on compilation and runtime environments):
 -----+ 0x562c2fb40349: str = *s
 0000 | 41 42 43
 ```
+
+# Features
+
+- `std` (default): the macros, `Options` and everything else built on `std::io`. Without it, the
+  crate is `#![no_std]` and keeps `write_hex`, `write_binspect_fmt!`, and the integrations
+  `as_bytes_pod`, `defmt_binspect!`, `log_binspect!` and `trace_binspect!`.
+- `alloc`: `format_binspect!` without `std`.
+- `fmt-core` and `std-io`: aliases for the core formatter, which is always built, and for `std`.
+- `analysis` (default): baselines, diffs, GPU buffer comparisons, snapshots, and cache line and SoA
+  reports.
+- `deep` (default): `graph!` and the `Deep` trait to dump the objects reachable from pointers.
+- `color`, `clipboard`, `derive`, `gzip`, `zstd` and `probe`, and the integrations
+  `bytemuck`, `bytes`, `defmt`, `log` and `tracing`: see the items which require them. `bytes`
+  requires `std` as well.
 */
 
-use std::any::type_name;
-use std::io::{self, Write};
-use std::mem;
-use std::ptr;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+/// Defines a function returning a process-wide value, created by `$init` on the first call, in
+/// place of `std::sync::OnceLock`, which is newer than the minimum supported Rust.
+#[cfg(feature = "std")]
+macro_rules! global {
+    ($(#[$attr: meta])* fn $name: ident() -> &'static $ty: ty = $init: expr;) => {
+        $(#[$attr])*
+        fn $name() -> &'static $ty {
+            use std::sync::atomic::{AtomicPtr, Ordering};
+            static INIT: std::sync::Once = std::sync::Once::new();
+            static VALUE: AtomicPtr<$ty> = AtomicPtr::new(std::ptr::null_mut());
+            INIT.call_once(|| VALUE.store(Box::into_raw(Box::new($init)), Ordering::SeqCst));
+            // The value is leaked, so it lives as long as the process.
+            unsafe { &*VALUE.load(Ordering::SeqCst) }
+        }
+    };
+}
+
+#[cfg(all(feature = "bytes", not(feature = "std")))]
+compile_error!("the `bytes` feature requires the `std` feature");
+
+use core::any::type_name;
+use core::mem;
+use core::ptr;
+
+#[cfg(feature = "std")]
+mod address;
+#[cfg(feature = "analysis")]
+mod baseline;
+#[cfg(feature = "std")]
+mod bitmap;
+#[cfg(feature = "std")]
+mod braille;
+#[cfg(all(feature = "bytes", feature = "std"))]
+pub mod buf;
+#[cfg(feature = "analysis")]
+mod cacheline;
+#[cfg(feature = "std")]
+mod capture;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+#[cfg(feature = "color")]
+mod color;
+#[cfg(feature = "deep")]
+mod deep;
+#[cfg(feature = "analysis")]
+mod diff;
+#[cfg(feature = "std")]
+mod display;
+#[cfg(feature = "std")]
+mod drops;
+#[cfg(feature = "std")]
+mod dump;
+#[cfg(feature = "std")]
+mod dumper;
+#[cfg(feature = "defmt")]
+mod embedded;
+#[cfg(feature = "std")]
+mod filter;
+#[cfg(feature = "std")]
+mod fixed;
+#[cfg(feature = "analysis")]
+mod gpu;
+#[cfg(feature = "std")]
+mod half;
+mod hex;
+#[cfg(feature = "std")]
+mod init;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod keep;
+#[cfg(feature = "std")]
+mod lane;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+mod limit;
+#[cfg(feature = "log")]
+mod logger;
+#[cfg(feature = "std")]
+mod merge;
+#[cfg(feature = "std")]
+mod normalize;
+#[cfg(feature = "bytemuck")]
+mod pod;
+#[cfg(feature = "std")]
+mod pointer;
+#[cfg(feature = "std")]
+mod poison;
+#[cfg(feature = "probe")]
+mod probe;
+#[cfg(feature = "std")]
+mod raw;
+#[cfg(feature = "std")]
+mod region;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "std")]
+mod shadow;
+#[cfg(feature = "std")]
+mod sink;
+#[cfg(feature = "analysis")]
+mod snapshot;
+#[cfg(feature = "analysis")]
+mod soa;
+#[cfg(feature = "std")]
+mod spill;
+#[cfg(feature = "std")]
+mod table;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "std")]
+mod varint;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+mod wasm;
+#[cfg(feature = "std")]
+mod yaml;
+
+#[cfg(feature = "std")]
+pub use crate::address::{AddressFormat, FormatAddress, SequentialIds, TagScheme};
+#[cfg(feature = "analysis")]
+pub use crate::baseline::baseline_dir;
+#[cfg(feature = "analysis")]
+#[doc(hidden)]
+pub use crate::baseline::{print_baseline_internal, write_baseline_internal};
+#[cfg(feature = "analysis")]
+#[doc(hidden)]
+pub use crate::cacheline::{
+    entry as cacheline_entry, print_cachelines_internal, write_cachelines_internal,
+};
+#[cfg(feature = "std")]
+pub use crate::capture::{capture, with_sink};
+#[cfg(feature = "clipboard")]
+pub use crate::clipboard::copy_to_clipboard;
+#[cfg(feature = "color")]
+pub use crate::color::{ColorChoice, ColorTheme};
+#[cfg(feature = "deep")]
+pub use crate::deep::{Deep, Walker};
+#[cfg(feature = "analysis")]
+pub use crate::diff::write_bit_diff;
+#[cfg(feature = "analysis")]
+#[doc(hidden)]
+pub use crate::diff::{assert_eq_internal, print_diff_internal, write_diff_internal};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::display::write_fmt_with_internal;
+#[cfg(feature = "std")]
+pub use crate::display::{Dump, Hex};
+#[cfg(feature = "std")]
+pub use crate::drops::{DropTracer, Traced};
+#[cfg(feature = "std")]
+pub use crate::dump::*;
+#[cfg(feature = "std")]
+pub use crate::dumper::Dumper;
+#[cfg(feature = "std")]
+pub use crate::filter::{clear_type_filters, hide_types, show_types};
+#[cfg(feature = "std")]
+pub use crate::fixed::Fixed;
+#[cfg(feature = "analysis")]
+pub use crate::gpu::{write_device_diff, GpuLayout};
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub use crate::hex::format_internal;
+#[cfg(feature = "std")]
+pub use crate::hex::parse_hex;
+pub use crate::hex::write_hex;
+#[doc(hidden)]
+pub use crate::hex::{write_fmt_internal, FmtDump};
+#[cfg(feature = "std")]
+pub use crate::init::{init, init_from_env};
+#[cfg(feature = "std")]
+pub use crate::json::{check_format_version, FORMAT_VERSION};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::keep::keep_internal;
+#[cfg(feature = "std")]
+pub use crate::keep::{dump_kept_on_panic, keep, print_kept, write_kept};
+#[cfg(feature = "std")]
+pub use crate::lane::Lane;
+#[cfg(feature = "std")]
+pub use crate::limit::{max_output, output_written, set_max_output};
+#[cfg(feature = "std")]
+pub use crate::merge::Merge;
+#[cfg(feature = "std")]
+pub use crate::normalize::normalize;
+#[cfg(feature = "bytemuck")]
+pub use crate::pod::as_bytes_pod;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::pointer::{
+    print_ptr_internal, print_span_internal, write_ptr_internal, write_span_internal,
+};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::poison::poison_options;
+#[cfg(feature = "std")]
+pub use crate::poison::{poison, poison_uninit, POISON};
+#[cfg(feature = "probe")]
+#[doc(hidden)]
+pub use crate::probe::{print_probe_internal, write_probe_internal};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::raw::write_raw_internal;
+#[cfg(feature = "std")]
+pub use crate::raw::RawWrite;
+#[cfg(feature = "std")]
+pub use crate::region::{name_region, unname_region};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::registry::options_for;
+#[cfg(feature = "std")]
+pub use crate::registry::{register_options, unregister_options};
+#[cfg(feature = "std")]
+pub use crate::render::{DumpInfo, Renderer};
+#[cfg(feature = "std")]
+pub use crate::report::ReportBuilder;
+#[cfg(feature = "std")]
+pub use crate::schema::Binspect;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::schema::{SchemaProbe, ViaBinspect, ViaNone};
+#[cfg(feature = "std")]
+pub use crate::shadow::ShadowTracked;
+#[cfg(feature = "std")]
+pub use crate::sink::{remove_sink, set_sink};
+#[cfg(feature = "analysis")]
+pub use crate::snapshot::Snapshot;
+#[cfg(feature = "analysis")]
+pub use crate::soa::{print_soa_report, write_soa_report};
+#[cfg(feature = "std")]
+pub use crate::spill::{Compression, Spill};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use crate::table::{print_table_internal, row as table_row, write_table_internal};
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use crate::trace::HexBody;
+#[cfg(feature = "std")]
+pub use crate::varint::Varints;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+pub use crate::wasm::linear_memory;
+#[cfg(feature = "derive")]
+pub use binspect_derive::Binspect;
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+pub use defmt;
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log;
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing;
 
 #[inline]
 #[doc(hidden)]
@@ -38,102 +314,70 @@ pub unsafe fn as_bytes_with_len<T: ?Sized>(t: &T, len: usize) -> &[u8] {
 
 #[inline]
 #[doc(hidden)]
-pub fn as_bytes<T: ?Sized>(t: &T) -> &[u8] {
-    unsafe { as_bytes_with_len(t, mem::size_of_val::<T>(t)) }
-}
-
-#[doc(hidden)]
-pub struct Record<'a, T: ?Sized> {
-    pub reference: &'a T,
-    pub bytes: &'a [u8],
-    pub sized: bool,
-    pub source: &'a str,
-    pub label: Option<&'a str>,
-    pub file: &'a str,
-    pub line: u32,
-    pub column: u32,
-}
-
-#[doc(hidden)]
-pub fn write_internal<W: Write, T: ?Sized>(
-    mut w: W,
-    record: &Record<T>,
-    absolute: bool,
-) -> Result<(), io::Error> {
-    let width = 16;
-    let center = width / 2;
-    if absolute {
-        writeln!(
-            w,
-            "{:p} : {} = {}",
-            record.reference,
-            type_name::<T>(),
-            record.source
-        )?;
-    } else {
-        writeln!(
-            w,
-            "-----+ {:p}: {} = {}",
-            record.reference,
-            type_name::<T>(),
-            record.source
-        )?;
-    }
-    for (i, x) in record.bytes.iter().enumerate() {
-        if i % width == 0 {
-            if i != 0 {
-                writeln!(w)?;
-            }
-            if absolute {
-                write!(w, "{:p} |", unsafe {
-                    (record.reference as *const _ as *const u8).add(i)
-                })?;
-            } else {
-                write!(w, "{:04x} |", i)?;
-            }
-        } else if i % center == 0 {
-            write!(w, " :")?;
-        }
-        write!(w, " {:02x}", x)?;
-    }
-    if !record.bytes.is_empty() {
-        writeln!(w)?;
-    }
-    Ok(())
+pub fn type_name_of<T: ?Sized>(_: &T) -> &'static str {
+    type_name::<T>()
 }
 
 #[inline]
 #[doc(hidden)]
-pub fn print_internal<T: ?Sized>(record: &Record<T>, absolute: bool) {
-    write_internal(io::stdout().lock(), record, absolute).unwrap()
+pub fn as_bytes<T: ?Sized>(t: &T) -> &[u8] {
+    unsafe { as_bytes_with_len(t, mem::size_of_val::<T>(t)) }
 }
 
-#[inline]
-#[doc(hidden)]
-pub fn eprint_internal<T: ?Sized>(record: &Record<T>, absolute: bool) {
-    write_internal(io::stderr().lock(), record, absolute).unwrap()
-}
+const WIDTH: usize = 16;
 
+#[cfg(feature = "std")]
 #[macro_export]
 #[doc(hidden)]
 macro_rules! record {
-    ($t: expr, $v: expr, $bs: expr, $sized: expr) => {{
+    // Without the schema of a `Binspect` type, which is built on the heap.
+    (@raw $t: expr, $v: expr, $bs: expr, $sized: expr) => {
+        $crate::record!(@schema None, $t, $v, $bs, $sized, None, &[])
+    };
+    (@schema $schema: expr, $t: expr, $v: expr, $bs: expr, $sized: expr, $label: expr, $meta: expr) => {{
         let bytes = $bs;
         $crate::Record {
             reference: $t,
             bytes,
             sized: $sized,
             source: stringify!($v),
-            label: None,
+            label: $label.into(),
             file: file!(),
             line: line!(),
             column: column!(),
+            schema: $schema,
+            meta: $meta,
         }
     }};
+    ($t: expr, $v: expr, $bs: expr, $sized: expr) => {
+        $crate::record!($t, $v, $bs, $sized, None)
+    };
+    ($t: expr, $v: expr, $bs: expr, $sized: expr, $label: expr) => {
+        $crate::record!($t, $v, $bs, $sized, $label, &[])
+    };
+    ($t: expr, $v: expr, $bs: expr, $sized: expr, $label: expr, $meta: expr) => {
+        $crate::record!(@schema {
+            #[allow(unused_imports)]
+            use $crate::{ViaBinspect as _, ViaNone as _};
+            (&$crate::SchemaProbe($t)).binspect_schema()
+        }, $t, $v, $bs, $sized, $label, $meta)
+    };
 }
 
 /// Prints the memory address and the hex representation of an object to stdout.
 ///
+/// A trailing `label = ".."` argument is shown in the header, which helps to tell many dumps
+/// apart. The macros writing dumps elsewhere accept it too.
+///
+/// Values such as generation counters or the state of a state machine can be attached with a
+/// trailing `meta = { "key" => value, .. }` argument, after the label if any. The values implement
+/// [`Display`](std::fmt::Display), and are shown in the header and included in the YAML and JSON
+/// formats. [`ebinspect!`], [`write_binspect!`] and the `_with` macros accept it too.
+///
+/// With a leading `move`, the value is moved into the macro, dumped and returned like
+/// [`dbg!`], so that it can be inspected in the middle of an expression. The value must be sized.
+/// [`ebinspect!`], [`binspect_with!`] and [`ebinspect_with!`] accept it too.
+///
 /// # Examples
 ///
 /// ```
@@ -141,18 +385,121 @@ macro_rules! record {
 /// let s = "ABC";
 /// binspect!(s);
 /// binspect!(*s);
+/// binspect!(*s, label = "name");
+/// binspect!(s.len(), meta = { "gen" => 1 });
+///
+/// let n = binspect!(move s.len() * 2) + 1;
+/// assert_eq!(n, 7);
+/// ```
+///
+/// Several objects separated by `;` are dumped in order, each in its own dump:
+///
 /// ```
+/// # use binspect::binspect;
+/// let (a, b) = (1u8, [2u16; 2]);
+/// binspect!(a; b; b[1]);
+/// ```
+#[cfg(feature = "std")]
 #[macro_export]
 macro_rules! binspect {
+    (move $v: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::print_internal(
+                    &$crate::record!(t, $v, bs, true, $label),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    (move $v: expr, $len: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::print_internal(
+                    &$crate::record!(t, $v, bs, false, $label),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    (move $v: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::print_internal(
+                    &$crate::record!(t, $v, bs, true),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    (move $v: expr, $len: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::print_internal(
+                    &$crate::record!(t, $v, bs, false),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    ($v: expr, $(label = $label: expr,)? meta = { $($key: expr => $value: expr),* $(,)? }) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        #[allow(unused_variables)]
+        let label: Option<&str> = None;
+        $(let label: Option<&str> = $label.into();)?
+        let meta: &[(&str, &dyn ::std::fmt::Display)] = &[$(($key, &$value)),*];
+        $crate::print_internal(
+            &$crate::record!(t, $v, bs, true, label, meta),
+            &$crate::options_for(t),
+        );
+    }};
+    ($v: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(
+            &$crate::record!(t, $v, bs, true, $label),
+            &$crate::options_for(t),
+        );
+    }};
+    ($v: expr, $len: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::print_internal(
+            &$crate::record!(t, $v, bs, false, $label),
+            &$crate::options_for(t),
+        );
+    }};
     ($v: expr) => {{
         let t = &$v;
         let bs = $crate::as_bytes(t);
-        $crate::print_internal(&$crate::record!(t, $v, bs, true), false);
+        $crate::print_internal(
+            &$crate::record!(t, $v, bs, true),
+            &$crate::options_for(t),
+        );
     }};
     ($v: expr, $len: expr) => {{
         let t = &$v;
         let bs = $crate::as_bytes_with_len(t, $len);
-        $crate::print_internal(&$crate::record!(t, $v, bs, false), false);
+        $crate::print_internal(
+            &$crate::record!(t, $v, bs, false),
+            &$crate::options_for(t),
+        );
+    }};
+    ($($v: expr $(, $len: expr)?);+ $(;)?) => {{
+        $($crate::binspect!($v $(, $len)?);)+
     }};
 }
 
@@ -166,17 +513,107 @@ macro_rules! binspect {
 /// ebinspect!(s);
 /// ebinspect!(*s);
 /// ```
+#[cfg(feature = "std")]
 #[macro_export]
 macro_rules! ebinspect {
+    (move $v: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::eprint_internal(
+                    &$crate::record!(t, $v, bs, true, $label),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    (move $v: expr, $len: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::eprint_internal(
+                    &$crate::record!(t, $v, bs, false, $label),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    (move $v: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::eprint_internal(
+                    &$crate::record!(t, $v, bs, true),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    (move $v: expr, $len: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::eprint_internal(
+                    &$crate::record!(t, $v, bs, false),
+                    &$crate::options_for(t),
+                );
+                v
+            }
+        }
+    };
+    ($v: expr, $(label = $label: expr,)? meta = { $($key: expr => $value: expr),* $(,)? }) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        #[allow(unused_variables)]
+        let label: Option<&str> = None;
+        $(let label: Option<&str> = $label.into();)?
+        let meta: &[(&str, &dyn ::std::fmt::Display)] = &[$(($key, &$value)),*];
+        $crate::eprint_internal(
+            &$crate::record!(t, $v, bs, true, label, meta),
+            &$crate::options_for(t),
+        );
+    }};
+    ($v: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::eprint_internal(
+            &$crate::record!(t, $v, bs, true, $label),
+            &$crate::options_for(t),
+        );
+    }};
+    ($v: expr, $len: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::eprint_internal(
+            &$crate::record!(t, $v, bs, false, $label),
+            &$crate::options_for(t),
+        );
+    }};
     ($v: expr) => {{
         let t = &$v;
         let bs = $crate::as_bytes(t);
-        $crate::eprint_internal(&$crate::record!(t, $v, bs, true), false);
+        $crate::eprint_internal(
+            &$crate::record!(t, $v, bs, true),
+            &$crate::options_for(t),
+        );
     }};
     ($v: expr, $len: expr) => {{
         let t = &$v;
         let bs = $crate::as_bytes_with_len(t, $len);
-        $crate::eprint_internal(&$crate::record!(t, $v, bs, false), false);
+        $crate::eprint_internal(
+            &$crate::record!(t, $v, bs, false),
+            &$crate::options_for(t),
+        );
+    }};
+    ($($v: expr $(, $len: expr)?);+ $(;)?) => {{
+        $($crate::ebinspect!($v $(, $len)?);)+
     }};
 }
 
@@ -191,17 +628,582 @@ macro_rules! ebinspect {
 /// write_binspect!(&mut buf, s).unwrap();
 /// buf.clear();
 /// write_binspect!(&mut buf, *s).unwrap();
+/// buf.clear();
+/// write_binspect!(&mut buf, *s, label = "name").unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(": str = *s [name]\n0000 | 41 42 43\n"));
+///
+/// let (gen, state) = (3, "idle");
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, 7u8, label = "slot", meta = { "gen" => gen, "state" => state }).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(": u8 = 7u8 [slot] {gen: 3, state: idle}\n0000 | 07\n"));
+/// ```
+///
+/// Several objects separated by `;` are written in order, stopping at the first error:
+///
+/// ```
+/// # use binspect::write_binspect;
+/// let (a, b) = (1u8, 2u16);
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, a; b).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(": u8 = a\n0000 | 01\n-----+ 0x"));
+/// assert!(out.ends_with(": u16 = b\n0000 | 02 00\n"));
 /// ```
+#[cfg(feature = "std")]
 #[macro_export]
 macro_rules! write_binspect {
+    ($w: expr, $v: expr, $(label = $label: expr,)? meta = { $($key: expr => $value: expr),* $(,)? }) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        #[allow(unused_variables)]
+        let label: Option<&str> = None;
+        $(let label: Option<&str> = $label.into();)?
+        let meta: &[(&str, &dyn ::std::fmt::Display)] = &[$(($key, &$value)),*];
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true, label, meta),
+            &$crate::Options::default(),
+        )
+    }};
+    ($w: expr, $v: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true, $label),
+            &$crate::Options::default(),
+        )
+    }};
+    ($w: expr, $v: expr, $len: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, false, $label),
+            &$crate::Options::default(),
+        )
+    }};
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::Options::default(),
+        )
+    }};
+    ($w: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, false),
+            &$crate::Options::default(),
+        )
+    }};
+    ($w: expr, $($v: expr $(, $len: expr)?);+ $(;)?) => {
+        match $w {
+            mut w => {
+                let mut result = Ok(());
+                $(
+                    if result.is_ok() {
+                        result = $crate::write_binspect!(&mut w, $v $(, $len)?);
+                    }
+                )+
+                result
+            }
+        }
+    };
+}
+
+/// Prints an object to stdout like [`binspect!`], with addresses and pointer-like bytes replaced by
+/// placeholders.
+///
+/// The output does not depend on where the object lives, so it can appear verbatim in doctests and
+/// in documentation. Aligned pointer-sized words whose value looks like a user-space address are
+/// printed as `##`; this is a heuristic and may also hide large integers.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_doc;
+/// let s = "ABC";
+/// binspect_doc!(s);
+/// binspect_doc!(*s);
+/// ```
+///
+/// ```text
+/// -----+ [addr]: &str = s
+/// 0000 | ## ## ## ## ## ## ## ## : 03 00 00 00 00 00 00 00
+/// -----+ [addr]: str = *s
+/// 0000 | 41 42 43
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! binspect_doc {
+    ($v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(&$crate::record!(t, $v, bs, true), &$crate::doc_options());
+    }};
+    ($v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::print_internal(&$crate::record!(t, $v, bs, false), &$crate::doc_options());
+    }};
+}
+
+/// Writes an object to [`std::io::Write`] like [`binspect_doc!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_doc;
+/// let s = "ABC";
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_doc!(&mut buf, *s).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "-----+ [addr]: str = *s\n0000 | 41 42 43\n"
+/// );
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_doc {
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::doc_options(),
+        )
+    }};
+    ($w: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, false),
+            &$crate::doc_options(),
+        )
+    }};
+}
+
+/// Prints an object to stdout like [`binspect!`], with the address of each line instead of its
+/// offset, as [`Options::absolute`] does.
+///
+/// This helps to correlate the output with addresses shown by a debugger.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_abs;
+/// let s = "ABC";
+/// binspect_abs!(s);
+/// binspect_abs!(*s);
+/// ```
+///
+/// ```text
+/// 0x7ffce3c8f7a0 : &str = s
+/// 0x7ffce3c8f7a0 | 49 03 b4 2f 2c 56 00 00 : 03 00 00 00 00 00 00 00
+/// 0x562c2fb40349 : str = *s
+/// 0x562c2fb40349 | 41 42 43
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! binspect_abs {
+    ($v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(&$crate::record!(t, $v, bs, true), &$crate::abs_options());
+    }};
+    ($v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::print_internal(&$crate::record!(t, $v, bs, false), &$crate::abs_options());
+    }};
+}
+
+/// Writes an object to [`std::io::Write`] like [`binspect_abs!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_abs;
+/// let s = "ABC";
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_abs!(&mut buf, *s).unwrap();
+/// let p = s.as_ptr();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     format!("{:p} : str = *s\n{:p} | 41 42 43\n", p, p)
+/// );
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_abs {
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::abs_options(),
+        )
+    }};
+    ($w: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, false),
+            &$crate::abs_options(),
+        )
+    }};
+}
+
+/// Prints an object to stdout as a JSON object on a single line, as [`Format::Json`] does.
+///
+/// The object has the keys `addr`, `region` if the address is in a named region, `type`, `size`,
+/// `src`, `label` if given, `file`, `line`, `column`, `time` with [`Options::timestamps`],
+/// `version`, the [`FORMAT_VERSION`], and `bytes`.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_json;
+/// let s = "ABC";
+/// binspect_json!(s);
+/// binspect_json!(*s);
+/// ```
+///
+/// ```text
+/// {"addr":"0x7ffce3c8f7a0","type":"&str","size":16,"src":"s","file":"src/main.rs","line":3,"column":1,"version":1,"bytes":[73,3,180,47,44,86,0,0,3,0,0,0,0,0,0,0]}
+/// {"addr":"0x562c2fb40349","type":"str","size":3,"src":"*s","file":"src/main.rs","line":4,"column":1,"version":1,"bytes":[65,66,67]}
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! binspect_json {
+    ($v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(&$crate::record!(t, $v, bs, true), &$crate::json_options());
+    }};
+    ($v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::print_internal(&$crate::record!(t, $v, bs, false), &$crate::json_options());
+    }};
+}
+
+/// Writes an object to [`std::io::Write`] like [`binspect_json!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_json;
+/// let s = "A\"C";
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_json!(&mut buf, *s).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.starts_with("{\"addr\":\"0x"));
+/// assert!(out.contains(",\"type\":\"str\",\"size\":3,\"src\":\"*s\",\"file\":"));
+/// assert!(out.ends_with(",\"bytes\":[65,34,67]}\n"));
+/// ```
+///
+/// Values attached with `meta = { .. }` are written as strings in a `meta` object:
+///
+/// ```
+/// # use binspect::{json_options, write_binspect_with};
+/// let gen = 2;
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &json_options(), 1u8, meta = { "gen" => gen }).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(",\"src\":\"1u8\",\"meta\":{\"gen\":\"2\"},\"file\":"));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_json {
     ($w: expr, $v: expr) => {{
         let t = &$v;
         let bs = $crate::as_bytes(t);
-        $crate::write_internal($w, &$crate::record!(t, $v, bs, true), false)
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::json_options(),
+        )
     }};
     ($w: expr, $v: expr, $len: expr) => {{
         let t = &$v;
         let bs = $crate::as_bytes_with_len(t, $len);
-        $crate::write_internal($w, &$crate::record!(t, $v, bs, false), false)
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, false),
+            &$crate::json_options(),
+        )
     }};
 }
+
+/// Prints an object to stdout like [`binspect!`] with the given [`Options`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{binspect_with, Header, Options};
+/// let options = Options {
+///     header: Header::KeyValue,
+///     ..Options::default()
+/// };
+/// let s = "ABC";
+/// binspect_with!(&options, s);
+/// binspect_with!(&options, *s);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! binspect_with {
+    ($o: expr, move $v: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::print_internal(&$crate::record!(t, $v, bs, true, $label), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, move $v: expr, $len: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::print_internal(&$crate::record!(t, $v, bs, false, $label), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, move $v: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::print_internal(&$crate::record!(t, $v, bs, true), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, move $v: expr, $len: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::print_internal(&$crate::record!(t, $v, bs, false), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, $v: expr, $(label = $label: expr,)? meta = { $($key: expr => $value: expr),* $(,)? }) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        #[allow(unused_variables)]
+        let label: Option<&str> = None;
+        $(let label: Option<&str> = $label.into();)?
+        let meta: &[(&str, &dyn ::std::fmt::Display)] = &[$(($key, &$value)),*];
+        $crate::print_internal(&$crate::record!(t, $v, bs, true, label, meta), $o);
+    }};
+    ($o: expr, $v: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(&$crate::record!(t, $v, bs, true, $label), $o);
+    }};
+    ($o: expr, $v: expr, $len: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::print_internal(&$crate::record!(t, $v, bs, false, $label), $o);
+    }};
+    ($o: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(&$crate::record!(t, $v, bs, true), $o);
+    }};
+    ($o: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::print_internal(&$crate::record!(t, $v, bs, false), $o);
+    }};
+    ($o: expr, $($v: expr $(, $len: expr)?);+ $(;)?) => {
+        match $o {
+            o => {
+                $($crate::binspect_with!(o, $v $(, $len)?);)+
+            }
+        }
+    };
+}
+
+/// Prints an object to stderr like [`ebinspect!`] with the given [`Options`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{ebinspect_with, Options};
+/// let options = Options {
+///     absolute: true,
+///     ..Options::default()
+/// };
+/// let s = "ABC";
+/// ebinspect_with!(&options, s);
+/// ebinspect_with!(&options, *s);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! ebinspect_with {
+    ($o: expr, move $v: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::eprint_internal(&$crate::record!(t, $v, bs, true, $label), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, move $v: expr, $len: expr, label = $label: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::eprint_internal(&$crate::record!(t, $v, bs, false, $label), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, move $v: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes(t);
+                $crate::eprint_internal(&$crate::record!(t, $v, bs, true), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, move $v: expr, $len: expr) => {
+        match $v {
+            v => {
+                let t = &v;
+                let bs = $crate::as_bytes_with_len(t, $len);
+                $crate::eprint_internal(&$crate::record!(t, $v, bs, false), $o);
+                v
+            }
+        }
+    };
+    ($o: expr, $v: expr, $(label = $label: expr,)? meta = { $($key: expr => $value: expr),* $(,)? }) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        #[allow(unused_variables)]
+        let label: Option<&str> = None;
+        $(let label: Option<&str> = $label.into();)?
+        let meta: &[(&str, &dyn ::std::fmt::Display)] = &[$(($key, &$value)),*];
+        $crate::eprint_internal(&$crate::record!(t, $v, bs, true, label, meta), $o);
+    }};
+    ($o: expr, $v: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::eprint_internal(&$crate::record!(t, $v, bs, true, $label), $o);
+    }};
+    ($o: expr, $v: expr, $len: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::eprint_internal(&$crate::record!(t, $v, bs, false, $label), $o);
+    }};
+    ($o: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::eprint_internal(&$crate::record!(t, $v, bs, true), $o);
+    }};
+    ($o: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::eprint_internal(&$crate::record!(t, $v, bs, false), $o);
+    }};
+    ($o: expr, $($v: expr $(, $len: expr)?);+ $(;)?) => {
+        match $o {
+            o => {
+                $($crate::ebinspect_with!(o, $v $(, $len)?);)+
+            }
+        }
+    };
+}
+
+/// Writes an object to [`std::io::Write`] like [`write_binspect!`] with the given [`Options`], or
+/// any other [`Renderer`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Header, Options};
+/// let options = Options {
+///     header: Header::KeyValue,
+///     placeholders: true,
+///     ..Options::default()
+/// };
+/// let s = "ABC";
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, *s).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.starts_with("addr=[addr] type=str size=3 src=\"*s\" file="));
+/// assert!(out.ends_with("\n0000 | 41 42 43\n"));
+///
+/// // Values are escaped and keys are sanitized, so each dump keeps a single header line.
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, 0u8, meta = { "a b=" => "x\ny\\" }).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(" meta.a_b_=\"x\\ny\\\\\" file="));
+/// assert_eq!(out.lines().count(), 2);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_with {
+    ($w: expr, $o: expr, $v: expr, $(label = $label: expr,)? meta = { $($key: expr => $value: expr),* $(,)? }) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        #[allow(unused_variables)]
+        let label: Option<&str> = None;
+        $(let label: Option<&str> = $label.into();)?
+        let meta: &[(&str, &dyn ::std::fmt::Display)] = &[$(($key, &$value)),*];
+        $crate::write_internal($w, &$crate::record!(t, $v, bs, true, label, meta), $o)
+    }};
+    ($w: expr, $o: expr, $v: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal($w, &$crate::record!(t, $v, bs, true, $label), $o)
+    }};
+    ($w: expr, $o: expr, $v: expr, $len: expr, label = $label: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_internal($w, &$crate::record!(t, $v, bs, false, $label), $o)
+    }};
+    ($w: expr, $o: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal($w, &$crate::record!(t, $v, bs, true), $o)
+    }};
+    ($w: expr, $o: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_internal($w, &$crate::record!(t, $v, bs, false), $o)
+    }};
+    ($w: expr, $o: expr, $($v: expr $(, $len: expr)?);+ $(;)?) => {
+        match ($w, $o) {
+            (mut w, o) => {
+                let mut result = Ok(());
+                $(
+                    if result.is_ok() {
+                        result = $crate::write_binspect_with!(&mut w, o, $v $(, $len)?);
+                    }
+                )+
+                result
+            }
+        }
+    };
+}
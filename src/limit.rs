@@ -0,0 +1,119 @@
+use std::env;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Once;
+
+use crate::capture;
+
+const UNLIMITED: usize = usize::max_value();
+
+static INIT: Once = Once::new();
+static LIMIT: AtomicUsize = AtomicUsize::new(UNLIMITED);
+static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+fn init() {
+    INIT.call_once(|| {
+        if let Some(limit) = env::var("BINSPECT_MAX_OUTPUT")
+            .ok()
+            .and_then(|s| parse_size(&s))
+        {
+            LIMIT.store(limit, Ordering::SeqCst);
+        }
+    });
+}
+
+/// Parses a byte count such as `1048576`, `512K` or `10MB`.
+///
+/// The suffixes `K`, `M` and `G` are powers of 1024 and may be followed by `B` or `iB`. Suffixes
+/// are case-insensitive, so `10mb` and `512k` are accepted too.
+pub(crate) fn parse_size(s: &str) -> Option<usize> {
+    let s = s.trim().to_ascii_lowercase();
+    let s = s.trim_end_matches("ib").trim_end_matches('b');
+    let (digits, unit) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&s[..i], c),
+        _ => (s, ' '),
+    };
+    let shift = match unit {
+        ' ' => 0,
+        'k' => 10,
+        'm' => 20,
+        'g' => 30,
+        _ => return None,
+    };
+    let n: usize = digits.trim().parse().ok()?;
+    n.checked_mul(1 << shift)
+}
+
+/// Sets the maximum number of bytes printed by [`binspect!`](crate::binspect) and
+/// [`ebinspect!`](crate::ebinspect) in this process, or removes the limit with `None`.
+///
+/// The default is read from the `BINSPECT_MAX_OUTPUT` environment variable, e.g. `10MB`. Once the
+/// limit is reached, a single warning is printed to stderr and further dumps are suppressed. A dump
+/// is never cut in the middle, so the output may exceed the limit by at most one dump.
+///
+/// Dumps written with [`write_binspect!`](crate::write_binspect) are not counted.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{binspect, output_written, set_max_output};
+///
+/// set_max_output(Some(64));
+/// let v = [0u8; 64];
+/// binspect!(v);
+/// let written = output_written();
+/// binspect!(v);
+/// assert_eq!(output_written(), written);
+/// set_max_output(None);
+/// ```
+pub fn set_max_output(limit: Option<usize>) {
+    init();
+    LIMIT.store(limit.unwrap_or(UNLIMITED), Ordering::SeqCst);
+    WARNED.store(false, Ordering::SeqCst);
+}
+
+/// Returns the maximum number of bytes printed in this process, if any.
+pub fn max_output() -> Option<usize> {
+    init();
+    match LIMIT.load(Ordering::SeqCst) {
+        UNLIMITED => None,
+        limit => Some(limit),
+    }
+}
+
+/// Returns the number of bytes printed so far in this process.
+pub fn output_written() -> usize {
+    WRITTEN.load(Ordering::SeqCst)
+}
+
+/// Runs `f` with a buffer, then writes what `f` wrote to `w`, or to the sinks of `w`, with a single
+/// write, so that the dumps of concurrent threads do not interleave. Nothing is run once the output
+/// limit has been reached. The output redirected by [`with_sink`](crate::with_sink) is neither
+/// limited nor counted.
+pub(crate) fn limited<W, F>(w: W, f: F) -> Result<(), io::Error>
+where
+    W: Write,
+    F: FnOnce(&mut Vec<u8>) -> Result<(), io::Error>,
+{
+    let redirected = capture::is_redirected();
+    if !redirected {
+        let limit = max_output().unwrap_or(UNLIMITED);
+        if WRITTEN.load(Ordering::SeqCst) >= limit {
+            if !WARNED.swap(true, Ordering::SeqCst) {
+                eprintln!(
+                    "binspect: output limit of {} bytes reached; further dumps are suppressed",
+                    limit
+                );
+            }
+            return Ok(());
+        }
+    }
+    let mut buf: Vec<u8> = vec![];
+    let result = f(&mut buf);
+    capture::output(w).write_all(&buf)?;
+    if !redirected {
+        WRITTEN.fetch_add(buf.len(), Ordering::SeqCst);
+    }
+    result
+}
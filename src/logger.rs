@@ -0,0 +1,61 @@
+/// Logs the dump of an object at the given [`log::Level`] through the `log` facade (requires the
+/// `log` feature).
+///
+/// The dump is formatted only if the level is enabled, and without the newline at its end. The
+/// target is the module of the caller as for [`log::log!`]. Without the `std` feature, only the
+/// header and the lines are logged, as [`write_hex`](crate::write_hex) writes them.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::log_binspect;
+/// use log::Level;
+///
+/// let header = [0xcau8, 0xfe, 0x00, 0x01];
+/// log_binspect!(Level::Debug, header);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! log_binspect {
+    ($level: expr, $v: expr $(, $len: expr)?) => {
+        $crate::log::log!(
+            $level,
+            "{}",
+            $crate::binspect_args!($v $(, $len)?).without_newline()
+        )
+    };
+}
+
+/// Logs the dump of an object at the given `log::Level` through the `log` facade.
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! log_binspect {
+    ($level: expr, $v: expr) => {
+        match &$v {
+            t => $crate::log::log!(
+                $level,
+                "{}",
+                $crate::FmtDump {
+                    address: t as *const _ as *const u8,
+                    type_name: $crate::type_name_of(t),
+                    source: stringify!($v),
+                    bytes: $crate::as_bytes(t),
+                }
+            ),
+        }
+    };
+    ($level: expr, $v: expr, $len: expr) => {
+        match &$v {
+            t => $crate::log::log!(
+                $level,
+                "{}",
+                $crate::FmtDump {
+                    address: t as *const _ as *const u8,
+                    type_name: $crate::type_name_of(t),
+                    source: stringify!($v),
+                    bytes: $crate::as_bytes_with_len(t, $len),
+                }
+            ),
+        }
+    };
+}
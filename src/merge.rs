@@ -0,0 +1,151 @@
+use std::io::{self, Write};
+
+use crate::json::{self, ParseError, Value};
+use crate::WIDTH;
+
+struct Entry {
+    log: usize,
+    time: u64,
+    address: String,
+    type_name: String,
+    source: String,
+    label: Option<String>,
+    bytes: Vec<Option<u8>>,
+}
+
+/// Interleaves the dumps logged by several processes in the JSON format by time, e.g. to follow
+/// a protocol over shared memory from both sides.
+///
+/// Each log holds a dump per line, as written with [`Format::Json`](crate::Format::Json) and
+/// [`Options::timestamps`](crate::Options::timestamps). A dump without `time` takes the time of
+/// the previous dump of its log. Dumps of the same time keep the order in which their logs were
+/// added.
+///
+/// ```text
+/// -----+ 0x7f3a5c000000: [u8; 4] = *req [client +0.000000s]
+/// 0000 | 01 00 00 00
+/// -----+ 0x7f9d10000000: [u8; 4] = *req [server +0.000120s]
+/// 0000 | 01 00 00 00
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use binspect::Merge;
+///
+/// let client = r#"{"addr":"0x1000","type":"u32","size":4,"src":"req","time":100,"bytes":[1,0,0,0]}
+/// {"addr":"0x1000","type":"u32","size":4,"src":"req","time":300,"bytes":[2,0,0,0]}"#;
+/// let server = r#"{"addr":"0x2000","type":"u32","size":4,"src":"ack","time":220,"bytes":[1,0,null,0]}"#;
+/// let mut merge = Merge::new();
+/// merge.add("client", client).unwrap().add("server", server).unwrap();
+/// let mut buf: Vec<u8> = vec![];
+/// merge.write(&mut buf).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "-----+ 0x1000: u32 = req [client +0.000000s]\n0000 | 01 00 00 00\n\
+///      -----+ 0x2000: u32 = ack [server +0.000120s]\n0000 | 01 00 ?? 00\n\
+///      -----+ 0x1000: u32 = req [client +0.000200s]\n0000 | 02 00 00 00\n"
+/// );
+/// ```
+#[derive(Default)]
+pub struct Merge {
+    logs: Vec<String>,
+    entries: Vec<Entry>,
+}
+
+impl Merge {
+    /// Creates an empty merge.
+    pub fn new() -> Merge {
+        Merge::default()
+    }
+
+    /// Adds the dumps of a log, named `name` in the output. Blank lines are skipped.
+    pub fn add(&mut self, name: &str, log: &str) -> Result<&mut Merge, ParseError> {
+        let index = self.logs.len();
+        let mut time = 0;
+        let mut entries = vec![];
+        for (n, line) in log.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = parse_entry(line, index, &mut time)
+                .map_err(|e| ParseError::new(format!("{} line {}: {}", name, n + 1, e)))?;
+            entries.push(entry);
+        }
+        self.logs.push(name.to_owned());
+        self.entries.extend(entries);
+        Ok(self)
+    }
+
+    /// Writes the dumps of all logs ordered by time, with the time since the first dump. Bytes
+    /// which were not logged are shown as `??`.
+    pub fn write<W: Write>(&self, mut w: W) -> Result<(), io::Error> {
+        let mut entries: Vec<&Entry> = self.entries.iter().collect();
+        entries.sort_by_key(|e| e.time);
+        let start = entries.first().map_or(0, |e| e.time);
+        for e in entries {
+            write!(w, "-----+ {}: {} = {}", e.address, e.type_name, e.source)?;
+            if let Some(label) = &e.label {
+                write!(w, " [{}]", label)?;
+            }
+            let elapsed = e.time - start;
+            writeln!(
+                w,
+                " [{} +{}.{:06}s]",
+                self.logs[e.log],
+                elapsed / 1_000_000,
+                elapsed % 1_000_000
+            )?;
+            for (r, row) in e.bytes.chunks(WIDTH).enumerate() {
+                write!(w, "{:04x} |", r * WIDTH)?;
+                for (j, x) in row.iter().enumerate() {
+                    if j == WIDTH / 2 {
+                        write!(w, " :")?;
+                    }
+                    match x {
+                        Some(x) => write!(w, " {:02x}", x)?,
+                        None => write!(w, " ??")?,
+                    }
+                }
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the dumps of all logs ordered by time to stdout.
+    pub fn print(&self) {
+        self.write(crate::capture::output(io::stdout())).unwrap()
+    }
+}
+
+/// Reads a dump of the `log`-th log, updating `time` if the dump has one.
+fn parse_entry(line: &str, log: usize, time: &mut u64) -> Result<Entry, ParseError> {
+    let value = json::parse(line)?;
+    json::check_version(&value)?;
+    if let Some(t) = value.get("time").and_then(Value::as_usize) {
+        *time = t as u64;
+    }
+    let mut bytes = vec![];
+    for x in value.field("bytes", Value::as_array)? {
+        bytes.push(match x {
+            Value::Null => None,
+            x => match x.as_usize() {
+                Some(x) if x <= 0xff => Some(x as u8),
+                _ => return Err(ParseError::new("invalid byte")),
+            },
+        });
+    }
+    Ok(Entry {
+        log,
+        time: *time,
+        address: value.field("addr", Value::as_str)?.to_owned(),
+        type_name: value.field("type", Value::as_str)?.to_owned(),
+        source: value.field("src", Value::as_str)?.to_owned(),
+        label: value
+            .get("label")
+            .and_then(Value::as_str)
+            .map(str::to_owned),
+        bytes,
+    })
+}
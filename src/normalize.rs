@@ -0,0 +1,125 @@
+use std::mem;
+
+use crate::{is_user_address, WIDTH};
+
+/// Masks the run-dependent parts of dumps which were already rendered, so that they can be
+/// compared with saved output in tests.
+///
+/// Hex numbers which look like addresses, e.g. in headers and at the start of lines, are replaced
+/// with `[addr]`, and aligned words of the lines which look like pointers with `##`, as
+/// [`Options::placeholders`](crate::Options::placeholders) renders them. The `time` of JSON dumps
+/// becomes 0. ASCII columns and the bytes of JSON and YAML dumps are left as they are.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{normalize, write_binspect};
+///
+/// let s = "ABC";
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, s).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// if cfg!(target_pointer_width = "64") {
+///     assert_eq!(
+///         normalize(&out),
+///         "-----+ [addr]: &str = s\n0000 | ## ## ## ## ## ## ## ## : 03 00 00 00 00 00 00 00\n"
+///     );
+/// }
+/// ```
+pub fn normalize(dump: &str) -> String {
+    let mut out = String::with_capacity(dump.len());
+    for line in split_lines(dump) {
+        let line = mask_addresses(line);
+        out += &mask_pointers(&line);
+    }
+    out
+}
+
+/// Splits after each newline, keeping the newlines.
+fn split_lines(s: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '\n' {
+            lines.push(&s[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+    lines
+}
+
+/// Replaces the hex numbers which look like addresses with `[addr]`, and the digits of `"time":`
+/// with 0.
+fn mask_addresses(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(i) = rest.find("0x") {
+        let boundary = rest[..i]
+            .chars()
+            .last()
+            .map_or(true, |c| !c.is_ascii_alphanumeric());
+        let digits = rest[i + 2..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(rest.len() - i - 2);
+        let number = &rest[i + 2..i + 2 + digits];
+        out += &rest[..i];
+        match usize::from_str_radix(number, 16) {
+            Ok(value) if boundary && is_user_address(value) => out += "[addr]",
+            _ => out += &rest[i..i + 2 + digits],
+        }
+        rest = &rest[i + 2 + digits..];
+    }
+    out += rest;
+    if let Some(i) = out.find("\"time\":") {
+        let start = i + "\"time\":".len();
+        let end = out[start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(out.len(), |n| start + n);
+        out.replace_range(start..end, "0");
+    }
+    out
+}
+
+/// Replaces the aligned words of a line of a dump which look like pointers with `##`.
+fn mask_pointers(line: &str) -> String {
+    const SIZE: usize = mem::size_of::<usize>();
+    let bar = match line.find(" |") {
+        Some(i) if !line[..i].contains(' ') => i + 2,
+        _ => return line.to_owned(),
+    };
+    // The byte cells of the line, up to the first cell which is not a byte, e.g. of notes.
+    let mut cells: Vec<(usize, u8)> = vec![];
+    let mut pos = bar;
+    let bytes = line.as_bytes();
+    while cells.len() < WIDTH && bytes.get(pos) == Some(&b' ') {
+        let cell = match line.get(pos + 1..pos + 3) {
+            Some(cell) => cell,
+            None => break,
+        };
+        if cell.starts_with(':') {
+            pos += 2;
+            continue;
+        }
+        if !cell.chars().all(|c| c.is_ascii_hexdigit()) {
+            break;
+        }
+        cells.push((pos + 1, u8::from_str_radix(cell, 16).unwrap()));
+        pos += 3;
+    }
+    let mut out = line.to_owned();
+    for word in cells.chunks(SIZE).filter(|w| w.len() == SIZE) {
+        let mut buf = [0u8; SIZE];
+        for (b, (_, x)) in buf.iter_mut().zip(word) {
+            *b = *x;
+        }
+        if is_user_address(usize::from_ne_bytes(buf)) {
+            for (at, _) in word {
+                out.replace_range(*at..*at + 2, "##");
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,73 @@
+/// Returns the bytes of a plain-old-data value without `unsafe` (requires the `bytemuck`
+/// feature).
+///
+/// Dumping any value of a [`Pod`](bytemuck::Pod) type is always sound, since it has no padding
+/// and no invalid bit patterns. Other types go through [`as_bytes`](crate::as_bytes), which reads
+/// padding bytes as well.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::as_bytes_pod;
+/// let v = [0x0102u16, 0x0304];
+/// assert_eq!(as_bytes_pod(&v), &[0x02, 0x01, 0x04, 0x03]);
+/// ```
+pub fn as_bytes_pod<T: bytemuck::Pod>(t: &T) -> &[u8] {
+    bytemuck::bytes_of(t)
+}
+
+/// Prints a plain-old-data value to stdout like [`binspect!`](crate::binspect), reading its bytes
+/// with [`as_bytes_pod`] (requires the `bytemuck` feature).
+///
+/// Values of other types are rejected at compile time.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_pod;
+/// let v = [1.0f32, 2.0];
+/// binspect_pod!(v);
+/// ```
+///
+/// ```compile_fail
+/// # use binspect::binspect_pod;
+/// let s = String::from("ABC");
+/// binspect_pod!(s);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! binspect_pod {
+    ($v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_pod(t);
+        $crate::print_internal(
+            &$crate::record!(t, $v, bs, true),
+            &$crate::Options::default(),
+        );
+    }};
+}
+
+/// Writes a plain-old-data value to [`std::io::Write`] like [`binspect_pod!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_pod;
+/// let v = 0x0102_0304u32;
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_pod!(&mut buf, v).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(": u32 = v\n0000 | 04 03 02 01\n"));
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! write_binspect_pod {
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_pod(t);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::Options::default(),
+        )
+    }};
+}
@@ -0,0 +1,295 @@
+use std::any::type_name;
+use std::io::{self, Write};
+use std::mem;
+use std::slice;
+
+use crate::{filter, limit, DumpInfo, Options, Renderer};
+
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn write_ptr_internal<W: Write, T: ?Sized>(
+    mut w: W,
+    p: *const T,
+    len: usize,
+    source: &str,
+    file: &str,
+    line: u32,
+    column: u32,
+    options: &Options,
+) -> Result<(), io::Error> {
+    let info = DumpInfo {
+        address: p as *const u8,
+        type_name: type_name::<*const T>(),
+        size: len,
+        source,
+        label: None,
+        file,
+        line,
+        column,
+        schema: None,
+        meta: &[],
+    };
+    if info.address.is_null() {
+        options.header(&mut w, &info)?;
+        return writeln!(w, "     + null pointer");
+    }
+    let bytes = slice::from_raw_parts(info.address, len);
+    options.render(&mut w, &info, bytes)
+}
+
+#[doc(hidden)]
+pub unsafe fn print_ptr_internal<T: ?Sized>(
+    p: *const T,
+    len: usize,
+    source: &str,
+    file: &str,
+    line: u32,
+    column: u32,
+) {
+    if !filter::is_shown(type_name::<*const T>()) {
+        return;
+    }
+    let options = Options::default();
+    #[cfg(feature = "color")]
+    let options = crate::for_stream(&options, crate::color::Stream::Stdout).into_owned();
+    limit::limited(io::stdout(), |w| {
+        write_ptr_internal(w, p, len, source, file, line, column, &options)
+    })
+    .unwrap()
+}
+
+/// Prints `len` bytes at a raw pointer to stdout like [`binspect!`](crate::binspect), e.g. for
+/// buffers received over FFI or the internals of allocators, without creating a reference first.
+///
+/// The type in the header is the type of the pointer. A null pointer is printed as a header
+/// followed by `+ null pointer`.
+///
+/// # Safety
+///
+/// The macro must be called in an `unsafe` block. Unless it is null, the pointer must be valid
+/// for reads of `len` bytes.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_ptr;
+/// let buf = vec![1u8, 2, 3, 4];
+/// let p: *const u8 = buf.as_ptr();
+/// unsafe { binspect_ptr!(p, buf.len()) };
+/// unsafe { binspect_ptr!(std::ptr::null::<u32>(), 4) };
+/// ```
+#[macro_export]
+macro_rules! binspect_ptr {
+    ($p: expr, $len: expr) => {
+        $crate::print_ptr_internal($p, $len, stringify!($p), file!(), line!(), column!())
+    };
+}
+
+/// Writes `len` bytes at a raw pointer to [`std::io::Write`] like [`binspect_ptr!`].
+///
+/// # Safety
+///
+/// As for [`binspect_ptr!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_ptr;
+/// let value = 0x0102u16;
+/// let p = &value as *const u16;
+/// let mut buf: Vec<u8> = vec![];
+/// unsafe { write_binspect_ptr!(&mut buf, p, 2) }.unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(": *const u16 = p\n0000 | 02 01\n"));
+///
+/// let mut buf: Vec<u8> = vec![];
+/// unsafe { write_binspect_ptr!(&mut buf, std::ptr::null_mut::<u8>(), 16) }.unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(": *const u8 = std::ptr::null_mut::<u8>()\n     + null pointer\n"));
+/// ```
+#[macro_export]
+macro_rules! write_binspect_ptr {
+    ($w: expr, $p: expr, $len: expr) => {
+        $crate::write_ptr_internal(
+            $w,
+            $p,
+            $len,
+            stringify!($p),
+            file!(),
+            line!(),
+            column!(),
+            &$crate::Options::default(),
+        )
+    };
+}
+
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn write_span_internal<W: Write, A: ?Sized, B: ?Sized>(
+    mut w: W,
+    a: &A,
+    b: &B,
+    sources: (&str, &str),
+    file: &str,
+    line: u32,
+    column: u32,
+    options: &Options,
+) -> Result<(), io::Error> {
+    let a_start = a as *const A as *const u8 as usize;
+    let b_start = b as *const B as *const u8 as usize;
+    let (a_end, end) = (a_start + mem::size_of_val(a), b_start + mem::size_of_val(b));
+    let start = a_start.min(b_start);
+    let source = format!("{} .. {}", sources.0, sources.1);
+    let info = DumpInfo {
+        address: start as *const u8,
+        type_name: type_name::<(&A, &B)>(),
+        size: end.saturating_sub(start),
+        source: &source,
+        label: None,
+        file,
+        line,
+        column,
+        schema: None,
+        meta: &[],
+    };
+    if end < a_end {
+        options.header(&mut w, &info)?;
+        return writeln!(w, "     + {} ends before {}", sources.1, sources.0);
+    }
+    let bytes = slice::from_raw_parts(info.address, info.size);
+    options.render(&mut w, &info, bytes)?;
+    writeln!(
+        w,
+        "     + {:04x}..{:04x} {}",
+        a_start - start,
+        a_end - start,
+        sources.0
+    )?;
+    if b_start > a_end {
+        writeln!(
+            w,
+            "     + {:04x}..{:04x} gap of {} bytes",
+            a_end - start,
+            b_start - start,
+            b_start - a_end
+        )?;
+    }
+    writeln!(
+        w,
+        "     + {:04x}..{:04x} {}",
+        b_start - start,
+        end - start,
+        sources.1
+    )
+}
+
+#[doc(hidden)]
+pub unsafe fn print_span_internal<A: ?Sized, B: ?Sized>(
+    a: &A,
+    b: &B,
+    sources: (&str, &str),
+    file: &str,
+    line: u32,
+    column: u32,
+) {
+    if !filter::is_shown(type_name::<(&A, &B)>()) {
+        return;
+    }
+    let options = Options::default();
+    #[cfg(feature = "color")]
+    let options = crate::for_stream(&options, crate::color::Stream::Stdout).into_owned();
+    limit::limited(io::stdout(), |w| {
+        write_span_internal(w, a, b, sources, file, line, column, &options)
+    })
+    .unwrap()
+}
+
+/// Prints the memory from the start of `a` to the end of `b` to stdout like
+/// [`binspect!`](crate::binspect), followed by the ranges of both objects and of the gap between
+/// them, e.g. to see the padding between two fields of a struct. The type in the header is the
+/// pair of the types of the references.
+///
+/// If `b` starts before `a`, the dump starts at `b`. If `b` ends before `a` ends, only a header is printed, followed by `+ b ends before a`.
+///
+/// # Safety
+///
+/// The macro must be called in an `unsafe` block. `a` and `b` must be parts of the same object,
+/// such as fields of a struct or elements of an array, so that the memory between them is valid
+/// for reads.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_span;
+/// #[repr(C)]
+/// struct Header {
+///     kind: u8,
+///     len: u32,
+/// }
+///
+/// let h = Header { kind: 1, len: 2 };
+/// unsafe { binspect_span!(h.kind, h.len) };
+/// ```
+///
+/// An example of output:
+///
+/// ```text
+/// -----+ 0x7ffd4c1e66b0: (&u8, &u32) = h.kind .. h.len
+/// 0000 | 01 00 00 00 02 00 00 00
+///      + 0000..0001 h.kind
+///      + 0001..0004 gap of 3 bytes
+///      + 0004..0008 h.len
+/// ```
+#[macro_export]
+macro_rules! binspect_span {
+    ($a: expr, $b: expr $(,)?) => {
+        $crate::print_span_internal(
+            &$a,
+            &$b,
+            (stringify!($a), stringify!($b)),
+            file!(),
+            line!(),
+            column!(),
+        )
+    };
+}
+
+/// Writes the memory from the start of `a` to the end of `b` to [`std::io::Write`] like
+/// [`binspect_span!`].
+///
+/// # Safety
+///
+/// As for [`binspect_span!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_span;
+/// let a = [1u16, 2, 3, 4];
+/// let mut buf: Vec<u8> = vec![];
+/// unsafe { write_binspect_span!(&mut buf, a[0], a[2]) }.unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     ": (&u16, &u16) = a[0] .. a[2]\n\
+///      0000 | 01 00 02 00 03 00\n\
+///      \x20    + 0000..0002 a[0]\n\
+///      \x20    + 0002..0004 gap of 2 bytes\n\
+///      \x20    + 0004..0006 a[2]\n"
+/// ));
+///
+/// let mut buf: Vec<u8> = vec![];
+/// unsafe { write_binspect_span!(&mut buf, a[2], a[0]) }.unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with("\n     + a[0] ends before a[2]\n"));
+/// ```
+#[macro_export]
+macro_rules! write_binspect_span {
+    ($w: expr, $a: expr, $b: expr $(,)?) => {
+        $crate::write_span_internal(
+            $w,
+            &$a,
+            &$b,
+            (stringify!($a), stringify!($b)),
+            file!(),
+            line!(),
+            column!(),
+            &$crate::Options::default(),
+        )
+    };
+}
@@ -0,0 +1,84 @@
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+
+use crate::Options;
+
+/// The byte [`poison`] fills buffers with, which is rare in small integers and ASCII text, and
+/// is `10100101` in binary.
+pub const POISON: u8 = 0xa5;
+
+/// Fills `buf` with [`POISON`], so that a later dump with [`binspect_poisoned!`] shows which bytes
+/// a routine wrote.
+pub fn poison(buf: &mut [u8]) {
+    for x in buf {
+        *x = POISON;
+    }
+}
+
+/// Fills an uninitialized slot with [`POISON`] and returns it, e.g. before passing it to a
+/// routine which initializes it.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{poison_uninit, write_binspect_poisoned};
+/// use std::mem::MaybeUninit;
+///
+/// let mut slot = MaybeUninit::<[u16; 3]>::uninit();
+/// let p = poison_uninit(&mut slot).as_mut_ptr() as *mut u16;
+/// unsafe { p.add(1).write(1) };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_poisoned!(&mut buf, slot).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | ~~ ~~ 01 00 ~~ ~~\n     + 2 of 6 bytes written\n"
+/// ));
+/// ```
+pub fn poison_uninit<T>(slot: &mut MaybeUninit<T>) -> &mut MaybeUninit<T> {
+    unsafe { ptr::write_bytes(slot.as_mut_ptr() as *mut u8, POISON, mem::size_of::<T>()) };
+    slot
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn poison_options() -> Options {
+    Options {
+        poison: Some(POISON),
+        ..Options::default()
+    }
+}
+
+/// Prints an object filled by [`poison`] or [`poison_uninit`] to stdout, showing the bytes which
+/// were not written since as `~~`, as [`Options::poison`] does.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{binspect_poisoned, poison};
+/// let mut buf = vec![0u8; 32];
+/// poison(&mut buf);
+/// buf[..5].copy_from_slice(b"hello");
+/// binspect_poisoned!(*buf);
+/// ```
+#[macro_export]
+macro_rules! binspect_poisoned {
+    ($v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::print_internal(&$crate::record!(t, $v, bs, true), &$crate::poison_options());
+    }};
+}
+
+/// Writes an object filled by [`poison`] or [`poison_uninit`] to [`std::io::Write`] like
+/// [`binspect_poisoned!`].
+#[macro_export]
+macro_rules! write_binspect_poisoned {
+    ($w: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $v, bs, true),
+            &$crate::poison_options(),
+        )
+    }};
+}
@@ -0,0 +1,198 @@
+use std::any::type_name;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::{filter, limit, write_record, DumpInfo, Options};
+
+/// Granularity of probing, no larger than a page on any supported platform.
+const PAGE: usize = 4096;
+
+/// Copies `len` bytes at `addr` without touching unreadable memory. Returns the copy, where
+/// unreadable bytes are zero, and the ranges of unreadable bytes.
+fn capture(addr: usize, len: usize) -> Result<(Vec<u8>, Vec<Range<usize>>), io::Error> {
+    let mut buf = vec![0u8; len];
+    let mut unreadable: Vec<Range<usize>> = vec![];
+    let mut i = 0;
+    while i < len {
+        let p = addr.wrapping_add(i);
+        let end = i + (PAGE - p % PAGE).min(len - i);
+        if !read(p, &mut buf[i..end])? {
+            match unreadable.last_mut() {
+                Some(last) if last.end == i => last.end = end,
+                _ => unreadable.push(i..end),
+            }
+        }
+        i = end;
+    }
+    Ok((buf, unreadable))
+}
+
+/// Reads `buf.len()` bytes at `addr` of this process through the kernel, which fails instead of
+/// faulting on unmapped or protected memory. Returns whether the bytes could be read, or an error
+/// if the kernel does not allow reading memory this way, e.g. under seccomp.
+#[cfg(target_os = "linux")]
+fn read(addr: usize, buf: &mut [u8]) -> Result<bool, io::Error> {
+    use std::os::raw::{c_int, c_ulong, c_void};
+
+    /// The error of `process_vm_readv` for ranges which are not mapped or not readable.
+    const EFAULT: i32 = 14;
+
+    #[repr(C)]
+    struct IoVec {
+        iov_base: *mut c_void,
+        iov_len: usize,
+    }
+
+    extern "C" {
+        fn getpid() -> c_int;
+        fn process_vm_readv(
+            pid: c_int,
+            local_iov: *const IoVec,
+            liovcnt: c_ulong,
+            remote_iov: *const IoVec,
+            riovcnt: c_ulong,
+            flags: c_ulong,
+        ) -> isize;
+    }
+
+    let local = IoVec {
+        iov_base: buf.as_mut_ptr() as *mut c_void,
+        iov_len: buf.len(),
+    };
+    let remote = IoVec {
+        iov_base: addr as *mut c_void,
+        iov_len: buf.len(),
+    };
+    let n = unsafe { process_vm_readv(getpid(), &local, 1, &remote, 1, 0) };
+    if n >= 0 {
+        return Ok(n as usize == buf.len());
+    }
+    let e = io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(EFAULT) => Ok(false),
+        _ => Err(e),
+    }
+}
+
+/// Fails, as probing is not supported on this platform.
+#[cfg(not(target_os = "linux"))]
+fn read(_addr: usize, _buf: &mut [u8]) -> Result<bool, io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "probing memory is only supported on Linux",
+    ))
+}
+
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub fn write_probe_internal<W: Write, T: ?Sized>(
+    w: W,
+    p: *const T,
+    len: usize,
+    source: &str,
+    file: &str,
+    line: u32,
+    column: u32,
+    options: &Options,
+) -> Result<(), io::Error> {
+    let info = DumpInfo {
+        address: p as *const u8,
+        type_name: type_name::<*const T>(),
+        size: len,
+        source,
+        label: None,
+        file,
+        line,
+        column,
+        schema: None,
+        meta: &[],
+    };
+    let (bytes, unreadable) = capture(info.address as usize, len)?;
+    // The bytes are copied already, so they are not read again word by word.
+    let options = Options {
+        word_reads: false,
+        ..options.clone()
+    };
+    write_record(w, &info, &bytes, &options, &unreadable)
+}
+
+#[doc(hidden)]
+pub fn print_probe_internal<T: ?Sized>(
+    p: *const T,
+    len: usize,
+    source: &str,
+    file: &str,
+    line: u32,
+    column: u32,
+) {
+    if !filter::is_shown(type_name::<*const T>()) {
+        return;
+    }
+    let options = Options::default();
+    #[cfg(feature = "color")]
+    let options = crate::for_stream(&options, crate::color::Stream::Stdout).into_owned();
+    limit::limited(io::stdout(), |w| {
+        write_probe_internal(w, p, len, source, file, line, column, &options)
+    })
+    .unwrap()
+}
+
+/// Prints `len` bytes at a raw pointer to stdout like [`binspect_ptr!`](crate::binspect_ptr),
+/// without touching unreadable memory (requires the `probe` feature).
+///
+/// Memory is read through the kernel with `process_vm_readv`, so the pointer may point anywhere:
+/// the bytes which could not be read are shown as `??`, and their lines end with `(unmapped)`.
+/// This makes the macro safe to call, e.g. to explore around a pointer of unknown validity.
+///
+/// # Panics
+///
+/// Panics if memory cannot be probed, on platforms other than Linux or if the kernel does not
+/// allow `process_vm_readv`. Use [`write_binspect_probe!`] to handle these errors.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_probe;
+/// let buf = vec![1u8, 2, 3, 4];
+/// # #[cfg(target_os = "linux")]
+/// binspect_probe!(buf.as_ptr(), buf.len());
+/// ```
+#[macro_export]
+macro_rules! binspect_probe {
+    ($p: expr, $len: expr) => {
+        $crate::print_probe_internal($p, $len, stringify!($p), file!(), line!(), column!())
+    };
+}
+
+/// Writes `len` bytes at a raw pointer to [`std::io::Write`] like [`binspect_probe!`], or returns
+/// an error if memory cannot be probed on this platform.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_probe;
+/// // The first pages of the address space are never mapped on Linux.
+/// let unmapped = 0x1000 as *const u8;
+/// let mut buf: Vec<u8> = vec![];
+/// match write_binspect_probe!(&mut buf, unmapped, 4) {
+///     Ok(()) => assert!(String::from_utf8(buf)
+///         .unwrap()
+///         .ends_with("\n0000 | ?? ?? ?? ??                                        (unmapped)\n")),
+///     Err(e) => assert!(!cfg!(target_os = "linux") || e.raw_os_error().is_some()),
+/// }
+/// ```
+#[macro_export]
+macro_rules! write_binspect_probe {
+    ($w: expr, $p: expr, $len: expr) => {
+        $crate::write_probe_internal(
+            $w,
+            $p,
+            $len,
+            stringify!($p),
+            file!(),
+            line!(),
+            column!(),
+            &$crate::Options::default(),
+        )
+    };
+}
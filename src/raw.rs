@@ -0,0 +1,168 @@
+use std::io::{self, Write};
+
+use crate::{write_internal, Options, Record};
+
+/// A minimal infallible byte sink, such as a serial port or a raw file descriptor.
+///
+/// Closures taking `&[u8]` implement this trait.
+pub trait RawWrite {
+    fn write_raw(&mut self, bytes: &[u8]);
+}
+
+impl<F: FnMut(&[u8])> RawWrite for F {
+    fn write_raw(&mut self, bytes: &[u8]) {
+        self(bytes)
+    }
+}
+
+struct RawWriter<R>(R);
+
+impl<R: RawWrite> Write for RawWriter<R> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.0.write_raw(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+pub fn write_raw_internal<R: RawWrite, T: ?Sized>(r: R, record: &Record<T>) {
+    let options = Options {
+        regions: false,
+        ..Options::default()
+    };
+    // `RawWriter` never fails, so neither does rendering.
+    let _ = write_internal(RawWriter(r), record, &options);
+}
+
+/// Writes the memory address and the hex representation of an object to a [`RawWrite`].
+///
+/// Unlike the other macros, this touches no process-global state: it neither locks stdout or
+/// stderr nor reads the environment or any global configuration. This makes it usable before
+/// `main` and in early boot code, as long as the sink itself is.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_raw;
+/// let s = "ABC";
+/// let mut out: Vec<u8> = vec![];
+/// write_binspect_raw!(|bs: &[u8]| out.extend_from_slice(bs), *s);
+/// assert!(out.ends_with(b": str = *s\n0000 | 41 42 43\n"));
+/// ```
+///
+/// Type filters, options registered for the type and named regions are ignored as well:
+///
+/// ```
+/// use binspect::{hide_types, name_region, register_options, unname_region, write_binspect_raw};
+/// use binspect::{unregister_options, Options};
+///
+/// let words = [0x4142_u16; 2];
+/// let region = [0u8; 4];
+/// let pointer = &region as *const [u8; 4];
+/// hide_types("[u16; 2]");
+/// hide_types("*const *");
+/// register_options::<[u16; 2]>(Options {
+///     ascii: true,
+///     ..Options::default()
+/// });
+/// name_region(&words, 4, "words");
+/// name_region(&region, 4, "region");
+/// let mut out: Vec<u8> = vec![];
+/// write_binspect_raw!(|bs: &[u8]| out.extend_from_slice(bs), words);
+/// write_binspect_raw!(|bs: &[u8]| out.extend_from_slice(bs), pointer);
+/// let out = String::from_utf8(out).unwrap();
+/// # #[cfg(target_endian = "little")]
+/// assert!(out.contains(": [u16; 2] = words\n0000 | 42 41 42 41\n"), "{}", out);
+/// assert!(!out.contains("words)") && !out.contains("region"), "{}", out);
+/// assert_eq!(out.lines().count(), 4, "{}", out);
+/// # unname_region(&words);
+/// # unname_region(&region);
+/// # unregister_options::<[u16; 2]>();
+/// ```
+///
+/// Rendering performs no heap allocation either, so this can be called from inside a
+/// `#[global_allocator]` implementation to debug the allocator itself without recursion. This is
+/// only guaranteed for this macro: [`binspect!`](crate::binspect) and the other dump macros may
+/// allocate, e.g. to buffer each dump or to look up registered options. For the same reason, the
+/// fields of [`Binspect`](crate::Binspect) types are not listed, since their schema is built on
+/// the heap.
+///
+/// ```
+/// use binspect::write_binspect_raw;
+/// use std::alloc::{GlobalAlloc, Layout, System};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// struct Counting;
+///
+/// static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+///
+/// unsafe impl GlobalAlloc for Counting {
+///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+///         ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+///         System.alloc(layout)
+///     }
+///
+///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+///         System.dealloc(ptr, layout)
+///     }
+/// }
+///
+/// #[global_allocator]
+/// static ALLOCATOR: Counting = Counting;
+///
+/// struct Pair(u8, u8);
+///
+/// impl binspect::Binspect for Pair {
+///     fn schema(&self) -> binspect::schema::Schema {
+///         binspect::schema::Schema::new()
+///             .field("left", 0, 1, binspect::schema::Encoding::Skipped)
+///             .field("right", 1, 1, binspect::schema::Encoding::Skipped)
+///     }
+/// }
+///
+/// fn main() {
+///     let v = vec![0x41_u8; 20];
+///     let s = "ABC";
+///     let mut buf = [0u8; 1024];
+///     let mut len = 0;
+///     let before = ALLOCATIONS.load(Ordering::SeqCst);
+///     let mut sink = |bs: &[u8]| {
+///         buf[len..len + bs.len()].copy_from_slice(bs);
+///         len += bs.len();
+///     };
+///     write_binspect_raw!(&mut sink, v);
+///     write_binspect_raw!(&mut sink, *v);
+///     write_binspect_raw!(&mut sink, (1_u8, [2_u16; 40]));
+///     write_binspect_raw!(&mut sink, Pair(1, 2));
+///     #[cfg(feature = "derive")]
+///     {
+///         #[derive(binspect::Binspect)]
+///         #[repr(C)]
+///         struct Header {
+///             kind: u8,
+///             len: u32,
+///         }
+///         write_binspect_raw!(&mut sink, Header { kind: 1, len: 2 });
+///     }
+///     write_binspect_raw!(&mut sink, *s);
+///     assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before);
+///     assert!(buf[..len].ends_with(b"0000 | 41 42 43\n"));
+/// }
+/// ```
+#[macro_export]
+macro_rules! write_binspect_raw {
+    ($r: expr, $v: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes(t);
+        $crate::write_raw_internal($r, &$crate::record!(@raw t, $v, bs, true))
+    }};
+    ($r: expr, $v: expr, $len: expr) => {{
+        let t = &$v;
+        let bs = $crate::as_bytes_with_len(t, $len);
+        $crate::write_raw_internal($r, &$crate::record!(@raw t, $v, bs, false))
+    }};
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+struct Region {
+    start: usize,
+    len: usize,
+    name: String,
+}
+
+/// The number of named regions, to skip the lookup of addresses when there are none.
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+global! {
+    fn regions() -> &'static RwLock<Vec<Region>> = RwLock::new(vec![]);
+}
+
+/// Names the `len` bytes starting at `ptr`, so that dumps refer to addresses inside them by name.
+///
+/// Dumps of objects inside a named region show the name and the offset next to their address,
+/// and pointers to named regions in the dumped bytes are resolved at the end of their line. If
+/// regions overlap, the smallest one containing an address is used.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{name_region, unname_region, write_binspect};
+/// let pool = vec![0u32; 16];
+/// name_region(pool.as_ptr(), 64, "connection pool");
+///
+/// let conn = (&pool[2], 1u64);
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, pool[3]).unwrap();
+/// write_binspect!(&mut buf, conn).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(" (connection pool+0xc): u32 = pool[3]\n"));
+/// assert!(out.contains("  -> connection pool+0x8\n"));
+///
+/// unname_region(pool.as_ptr());
+/// ```
+///
+/// Regions may be named from anywhere, including from the writer a dump is written to:
+///
+/// ```
+/// # use binspect::{name_region, unname_region, write_binspect};
+/// use std::io::{self, Write};
+///
+/// struct Naming(Vec<u8>);
+///
+/// impl Write for Naming {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         name_region(self.0.as_ptr(), 1, "output");
+///         self.0.write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let x = 1u8;
+/// name_region(&x, 1, "x");
+/// let mut w = Naming(Vec::with_capacity(256));
+/// write_binspect!(&mut w, x).unwrap();
+/// assert!(String::from_utf8(w.0.clone()).unwrap().contains(" (x): u8 = x\n"));
+/// unname_region(&x);
+/// unname_region(w.0.as_ptr());
+/// ```
+pub fn name_region<T: ?Sized>(ptr: *const T, len: usize, name: &str) {
+    let start = ptr as *const u8 as usize;
+    let mut regions = regions().write().unwrap_or_else(|e| e.into_inner());
+    regions.retain(|r| r.start != start || r.len != len);
+    regions.push(Region {
+        start,
+        len,
+        name: name.to_owned(),
+    });
+    COUNT.store(regions.len(), Ordering::SeqCst);
+}
+
+/// Removes the names of the regions starting at `ptr`, e.g. before the memory is freed.
+pub fn unname_region<T: ?Sized>(ptr: *const T) {
+    let start = ptr as *const u8 as usize;
+    let mut regions = regions().write().unwrap_or_else(|e| e.into_inner());
+    regions.retain(|r| r.start != start);
+    COUNT.store(regions.len(), Ordering::SeqCst);
+}
+
+/// An address inside a named region.
+struct RegionRef<'a>(&'a str, usize);
+
+impl<'a> fmt::Display for RegionRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.1 == 0 {
+            f.write_str(self.0)
+        } else {
+            write!(f, "{}+{:#x}", self.0, self.1)
+        }
+    }
+}
+
+/// Returns the name and the offset of the smallest named region containing `addr`, if any, e.g.
+/// `pool+0x8`.
+///
+/// The name is copied before the lock is released, so that writers to which the result is
+/// rendered may name regions themselves.
+pub(crate) fn find(addr: usize) -> Option<String> {
+    if COUNT.load(Ordering::SeqCst) == 0 {
+        return None;
+    }
+    let (name, offset) = {
+        let regions = regions().read().unwrap_or_else(|e| e.into_inner());
+        let region = regions
+            .iter()
+            .filter(|r| r.start <= addr && addr - r.start < r.len)
+            .min_by_key(|r| r.len)?;
+        (region.name.clone(), addr - region.start)
+    };
+    Some(RegionRef(&name, offset).to_string())
+}
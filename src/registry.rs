@@ -0,0 +1,111 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::Options;
+
+/// Whether any options were registered, so that dumps do not lock the registry while it is empty.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+global! {
+    fn registry() -> &'static Mutex<HashMap<TypeId, Options>> = Mutex::new(HashMap::new());
+}
+
+fn lock() -> MutexGuard<'static, HashMap<TypeId, Options>> {
+    // Options are only cloned and replaced under the lock, so the poison is ignored.
+    registry().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Registers the options used instead of the default ones for the dumps of `T` by the macros
+/// printing without options, such as [`binspect!`](crate::binspect) and
+/// [`ebinspect!`](crate::ebinspect), so that common types are rendered appropriately everywhere
+/// without changing the call sites. Replaces the options registered before for `T`.
+///
+/// Like the rules of [`hide_types`](crate::hide_types), registered options never change the
+/// dumps written to an explicit writer, e.g. with [`write_binspect!`](crate::write_binspect).
+///
+/// Types are told apart by their `TypeId`, so `T` must be `'static`. The lifetimes of the
+/// dumped types are ignored, though: the options registered for `&'static str` apply to the
+/// dumps of `&str` with any lifetime, as there is no way to register them for a shorter one.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{binspect, capture, register_options, unregister_options, Options};
+///
+/// register_options::<[u8; 5]>(Options {
+///     ascii: true,
+///     ..Options::default()
+/// });
+/// let greeting = *b"Hello";
+/// let out = capture(|| binspect!(greeting));
+/// assert!(out.ends_with(
+///     ": [u8; 5] = greeting\n0000 | 48 65 6c 6c 6f                                     Hello\n"
+/// ));
+///
+/// let options = unregister_options::<[u8; 5]>().unwrap();
+/// assert!(options.ascii);
+/// ```
+///
+/// Borrowed types are registered with the `'static` lifetime:
+///
+/// ```
+/// use binspect::{binspect, capture, register_options, Options};
+///
+/// register_options::<&'static str>(Options {
+///     ascii: true,
+///     ..Options::default()
+/// });
+/// let owned = String::from("Hi");
+/// let s: &str = &owned;
+/// let out = capture(|| binspect!(*s; s));
+/// assert!(out.contains(": str = *s\n0000 | 48 69\n"));
+/// let line = out.split(": &str = s\n").nth(1).unwrap().lines().next().unwrap();
+/// assert!(line.chars().count() > 56, "{}", line);
+/// ```
+pub fn register_options<T: ?Sized + 'static>(options: Options) {
+    lock().insert(TypeId::of::<T>(), options);
+    ACTIVE.store(true, Ordering::SeqCst);
+}
+
+/// Removes the options registered for `T` by [`register_options`] and returns them, if any.
+pub fn unregister_options<T: ?Sized + 'static>() -> Option<Options> {
+    lock().remove(&TypeId::of::<T>())
+}
+
+#[doc(hidden)]
+pub fn options_for<T: ?Sized>(_: &T) -> Options {
+    if !ACTIVE.load(Ordering::SeqCst) {
+        return Options::default();
+    }
+    lock()
+        .get(&type_id_of::<T>())
+        .map_or_else(Options::default, |options| options.clone())
+}
+
+/// Returns the `TypeId` of `T` with its lifetimes replaced by `'static`.
+fn type_id_of<T: ?Sized>() -> TypeId {
+    trait NonStatic {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStatic for PhantomData<T> {
+        fn type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    let object: &dyn NonStatic = &phantom;
+    // Lifetimes do not exist at run time, so the `TypeId` is the same for all of them.
+    let object: &(dyn NonStatic + 'static) = unsafe { mem::transmute(object) };
+    object.type_id()
+}
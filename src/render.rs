@@ -0,0 +1,109 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::schema::Schema;
+use crate::WIDTH;
+
+/// An object being dumped, passed to the hooks of a [`Renderer`].
+#[derive(Clone, Copy)]
+pub struct DumpInfo<'a> {
+    /// The address of the object.
+    pub address: *const u8,
+    pub type_name: &'static str,
+    /// The number of bytes dumped.
+    pub size: usize,
+    /// The expression dumped, e.g. `*s`.
+    pub source: &'a str,
+    pub label: Option<&'a str>,
+    /// The location of the macro call.
+    pub file: &'a str,
+    pub line: u32,
+    pub column: u32,
+    /// The fields of the object if its type implements [`Binspect`](crate::Binspect).
+    pub schema: Option<&'a Schema>,
+    /// Key-value pairs given with `meta = { "key" => value }`, such as generation counters.
+    pub meta: &'a [(&'a str, &'a dyn fmt::Display)],
+}
+
+impl fmt::Debug for DumpInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let meta: Vec<(&str, String)> =
+            self.meta.iter().map(|(k, v)| (*k, v.to_string())).collect();
+        f.debug_struct("DumpInfo")
+            .field("address", &self.address)
+            .field("type_name", &self.type_name)
+            .field("size", &self.size)
+            .field("source", &self.source)
+            .field("label", &self.label)
+            .field("file", &self.file)
+            .field("line", &self.line)
+            .field("column", &self.column)
+            .field("schema", &self.schema)
+            .field("meta", &meta)
+            .finish()
+    }
+}
+
+/// Renders dumps in a format, passed to [`write_binspect_with!`](crate::write_binspect_with).
+///
+/// A dump is rendered as a header, lines of up to 16 bytes and a footer. [`Options`](crate::Options)
+/// is the renderer of the built-in formats.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, DumpInfo, Renderer};
+/// use std::io::{self, Write};
+///
+/// /// Renders each line as `type,offset,hex`.
+/// struct Csv;
+///
+/// impl Renderer for Csv {
+///     fn header(&self, _w: &mut dyn Write, _info: &DumpInfo) -> io::Result<()> {
+///         Ok(())
+///     }
+///
+///     fn line(&self, w: &mut dyn Write, info: &DumpInfo, offset: usize, bytes: &[u8]) -> io::Result<()> {
+///         write!(w, "{},{},", info.type_name, offset)?;
+///         for x in bytes {
+///             write!(w, "{:02x}", x)?;
+///         }
+///         writeln!(w)
+///     }
+/// }
+///
+/// let v = [7u8; 20];
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &Csv, v).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "[u8; 20],0,07070707070707070707070707070707\n[u8; 20],16,07070707\n"
+/// );
+/// ```
+pub trait Renderer {
+    /// Writes the start of a dump.
+    fn header(&self, w: &mut dyn Write, info: &DumpInfo) -> Result<(), io::Error>;
+
+    /// Writes a line of up to 16 bytes starting at `offset`.
+    fn line(
+        &self,
+        w: &mut dyn Write,
+        info: &DumpInfo,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), io::Error>;
+
+    /// Writes the end of a dump. Writes nothing by default.
+    fn footer(&self, _w: &mut dyn Write, _info: &DumpInfo) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    /// Writes a whole dump. By default, writes the header, the lines and the footer.
+    fn render(&self, w: &mut dyn Write, info: &DumpInfo, bytes: &[u8]) -> Result<(), io::Error> {
+        self.header(w, info)?;
+        for (r, row) in bytes.chunks(WIDTH).enumerate() {
+            self.line(w, info, r * WIDTH, row)?;
+        }
+        self.footer(w, info)
+    }
+}
@@ -0,0 +1,131 @@
+use std::io;
+
+enum Block {
+    Section(String),
+    Text(String),
+    Code(String, String),
+    Dump(String),
+}
+
+/// Builds a document of sections, code snippets and dumps, rendered to Markdown or HTML.
+///
+/// Consecutive dumps are gathered into one preformatted block, as in [examples.md].
+///
+/// [examples.md]: https://github.com/taskie/binspect/blob/main/examples.md
+///
+/// # Examples
+///
+/// ```
+/// use binspect::{write_binspect, ReportBuilder};
+///
+/// let mut report = ReportBuilder::new("Layout of core types");
+/// report.section("Strs").code("rust", "let s = \"ABC\";");
+/// let s = "ABC";
+/// report.dump_with(|w| write_binspect!(w, *s)).unwrap();
+/// let md = report.to_markdown();
+/// assert!(md.starts_with("# Layout of core types\n\n## Strs\n\n```rust\nlet s = \"ABC\";\n```\n"));
+/// assert!(md.contains(": str = *s\n0000 | 41 42 43\n```\n"));
+/// assert!(report.to_html().contains("<h2>Strs</h2>"));
+/// ```
+pub struct ReportBuilder {
+    title: String,
+    blocks: Vec<Block>,
+}
+
+impl ReportBuilder {
+    /// Creates an empty report with a title.
+    pub fn new(title: &str) -> ReportBuilder {
+        ReportBuilder {
+            title: title.to_owned(),
+            blocks: vec![],
+        }
+    }
+
+    /// Starts a new section.
+    pub fn section(&mut self, name: &str) -> &mut ReportBuilder {
+        self.blocks.push(Block::Section(name.to_owned()));
+        self
+    }
+
+    /// Adds a paragraph of plain text.
+    pub fn text(&mut self, text: &str) -> &mut ReportBuilder {
+        self.blocks.push(Block::Text(text.to_owned()));
+        self
+    }
+
+    /// Adds a code snippet in the given language.
+    pub fn code(&mut self, lang: &str, code: &str) -> &mut ReportBuilder {
+        self.blocks
+            .push(Block::Code(lang.to_owned(), code.trim_end().to_owned()));
+        self
+    }
+
+    /// Adds the output of a dump, such as the one written by [`write_binspect!`](crate::write_binspect).
+    pub fn dump(&mut self, output: &str) -> &mut ReportBuilder {
+        if let Some(Block::Dump(last)) = self.blocks.last_mut() {
+            last.push_str(output);
+        } else {
+            self.blocks.push(Block::Dump(output.to_owned()));
+        }
+        self
+    }
+
+    /// Adds the output written by `f` to a buffer as a dump.
+    pub fn dump_with<F>(&mut self, f: F) -> Result<&mut ReportBuilder, io::Error>
+    where
+        F: FnOnce(&mut Vec<u8>) -> Result<(), io::Error>,
+    {
+        let mut buf = vec![];
+        f(&mut buf)?;
+        Ok(self.dump(&String::from_utf8_lossy(&buf)))
+    }
+
+    /// Renders the report as Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut s = format!("# {}\n", self.title);
+        for block in &self.blocks {
+            s.push('\n');
+            match block {
+                Block::Section(name) => s += &format!("## {}\n", name),
+                Block::Text(text) => s += &format!("{}\n", text),
+                Block::Code(lang, code) => s += &format!("```{}\n{}\n```\n", lang, code),
+                Block::Dump(dump) => s += &format!("```text\n{}```\n", dump),
+            }
+        }
+        s
+    }
+
+    /// Renders the report as an HTML fragment.
+    pub fn to_html(&self) -> String {
+        let mut s = format!("<h1>{}</h1>\n", escape(&self.title));
+        for block in &self.blocks {
+            match block {
+                Block::Section(name) => s += &format!("<h2>{}</h2>\n", escape(name)),
+                Block::Text(text) => s += &format!("<p>{}</p>\n", escape(text)),
+                Block::Code(lang, code) => {
+                    s += &format!(
+                        "<pre><code class=\"language-{}\">{}</code></pre>\n",
+                        escape(lang),
+                        escape(code)
+                    )
+                }
+                Block::Dump(dump) => s += &format!("<pre>{}</pre>\n", escape(dump)),
+            }
+        }
+        s
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
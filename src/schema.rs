@@ -0,0 +1,768 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::json::{self, Value};
+use crate::{as_bytes, Fixed, WIDTH};
+
+pub use crate::json::ParseError;
+
+/// Byte order of a multi-byte field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Returns the byte order of the target.
+    pub fn native() -> Endian {
+        if cfg!(target_endian = "big") {
+            Endian::Big
+        } else {
+            Endian::Little
+        }
+    }
+}
+
+/// How the bytes of a [`Field`] are decoded.
+///
+/// Integers may be 1 to 8 bytes long and floats 2, 4 or 8 bytes long, 2 bytes for IEEE 754
+/// half-precision floats; other lengths are shown as raw bytes.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::schema::{Encoding, Endian, Schema};
+///
+/// let schema = Schema::new()
+///     .field("half", 0, 2, Encoding::Float(Endian::Little))
+///     .field("brain", 2, 2, Encoding::BFloat16(Endian::Little));
+/// let mut buf: Vec<u8> = vec![];
+/// schema.write(&mut buf, &[0x00, 0x3c, 0x80, 0x3f]).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.contains(" half: f16le = 1.0\n"));
+/// assert!(out.ends_with(" brain: bf16le = 1.0\n"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Raw bytes without a decoded value.
+    Bytes,
+    Unsigned(Endian),
+    Signed(Endian),
+    Float(Endian),
+    /// A bfloat16 of 2 bytes, the upper half of an `f32`.
+    BFloat16(Endian),
+    /// A fixed-point or scaled integer of 1 to 8 bytes.
+    Fixed(Endian, Fixed),
+    /// UTF-8 text, invalid sequences replaced by U+FFFD.
+    Utf8,
+    /// Bytes which must not be shown, such as secrets. Each byte is rendered as `**`.
+    Redacted,
+    /// Bytes which are not worth showing, such as large embedded buffers. The field is rendered as
+    /// a single line without bytes.
+    Skipped,
+    /// Bytes decoded by a user function.
+    Custom(Decoder),
+}
+
+/// A named function decoding the bytes of a field, for [`Encoding::Custom`].
+///
+/// # Examples
+///
+/// ```
+/// use binspect::schema::{Decoder, Encoding, Schema};
+///
+/// fn weekday(bytes: &[u8]) -> Option<String> {
+///     let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+///     names.get(*bytes.first()? as usize).map(|s| s.to_string())
+/// }
+///
+/// let schema = Schema::new()
+///     .field("day", 0, 1, Encoding::Custom(Decoder::new("weekday", weekday)))
+///     .field("hour", 1, 1, Encoding::Custom(Decoder::new("hour", |b| Some(b[0].to_string()))));
+/// let mut buf: Vec<u8> = vec![];
+/// schema.write(&mut buf, &[4, 17]).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "0000 | 04                                                 day: weekday = Fri\n\
+///      0001 | 11                                                 hour: hour = 17\n"
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct Decoder {
+    /// The name of the decoded type, shown after the name of the field.
+    pub name: &'static str,
+    /// Returns the decoded value, or `None` if the bytes are invalid.
+    pub decode: fn(&[u8]) -> Option<String>,
+}
+
+impl Decoder {
+    /// Creates a decoder.
+    pub fn new(name: &'static str, decode: fn(&[u8]) -> Option<String>) -> Decoder {
+        Decoder { name, decode }
+    }
+}
+
+impl fmt::Debug for Decoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Decoder").field("name", &self.name).finish()
+    }
+}
+
+impl PartialEq for Decoder {
+    fn eq(&self, other: &Decoder) -> bool {
+        self.name == other.name && self.decode as usize == other.decode as usize
+    }
+}
+
+impl Eq for Decoder {}
+
+/// A named range of bytes in a buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub offset: usize,
+    pub len: usize,
+    pub encoding: Encoding,
+}
+
+impl Field {
+    /// Returns the end offset (exclusive) of the field.
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    /// Decodes the bytes of the field, which must be exactly `self.len` bytes long.
+    ///
+    /// Returns `None` for [`Encoding::Bytes`], [`Encoding::Redacted`] and [`Encoding::Skipped`],
+    /// and for lengths the encoding does not support.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        if bytes.len() != self.len {
+            return None;
+        }
+        match self.encoding {
+            Encoding::Bytes | Encoding::Redacted | Encoding::Skipped => None,
+            Encoding::Unsigned(endian) => read_uint(bytes, endian).map(|x| x.to_string()),
+            Encoding::Signed(endian) => read_uint(bytes, endian).map(|x| {
+                let shift = 64 - 8 * bytes.len() as u32;
+                (((x << shift) as i64) >> shift).to_string()
+            }),
+            Encoding::Float(endian) => match (bytes.len(), read_uint(bytes, endian)) {
+                (2, Some(x)) => Some(format!("{:?}", crate::half::f16_to_f32(x as u16))),
+                (4, Some(x)) => Some(format!("{:?}", f32::from_bits(x as u32))),
+                (8, Some(x)) => Some(format!("{:?}", f64::from_bits(x))),
+                _ => None,
+            },
+            Encoding::BFloat16(endian) => match (bytes.len(), read_uint(bytes, endian)) {
+                (2, Some(x)) => Some(format!("{:?}", crate::half::bf16_to_f32(x as u16))),
+                _ => None,
+            },
+            Encoding::Fixed(endian, fixed) => {
+                read_uint(bytes, endian).map(|x| format!("{:?}", fixed.value(x, bytes.len())))
+            }
+            Encoding::Utf8 => Some(format!("{:?}", String::from_utf8_lossy(bytes))),
+            Encoding::Custom(decoder) => (decoder.decode)(bytes),
+        }
+    }
+
+    /// Returns the name, the type and the decoded value of the field, e.g. `length: u32le = 3`.
+    pub(crate) fn note(&self, bytes: &[u8]) -> String {
+        match self.decode(bytes) {
+            Some(value) => format!("{} = {}", self, value),
+            None if bytes.len() < self.len => format!("{} (truncated)", self),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    /// Formats the name and the type of the field, e.g. `length: u32le`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bits = self.len * 8;
+        let endian = |e: Endian| match e {
+            Endian::Little if self.len > 1 => "le",
+            Endian::Big if self.len > 1 => "be",
+            _ => "",
+        };
+        match self.encoding {
+            Encoding::Bytes => write!(f, "{}: [u8; {}]", self.name, self.len),
+            Encoding::Unsigned(e) => write!(f, "{}: u{}{}", self.name, bits, endian(e)),
+            Encoding::Signed(e) => write!(f, "{}: i{}{}", self.name, bits, endian(e)),
+            Encoding::Float(e) => write!(f, "{}: f{}{}", self.name, bits, endian(e)),
+            Encoding::BFloat16(e) => write!(f, "{}: bf16{}", self.name, endian(e)),
+            Encoding::Fixed(e, fixed) => {
+                write!(f, "{}: ", self.name)?;
+                fixed.write_type(f, self.len, endian(e))
+            }
+            Encoding::Utf8 => write!(f, "{}: utf8", self.name),
+            Encoding::Redacted => write!(f, "{}: [u8; {}] (redacted)", self.name, self.len),
+            Encoding::Skipped => write!(f, "{}: [u8; {}] (skipped)", self.name, self.len),
+            Encoding::Custom(decoder) => write!(f, "{}: {}", self.name, decoder.name),
+        }
+    }
+}
+
+fn read_uint(bytes: &[u8], endian: Endian) -> Option<u64> {
+    if bytes.is_empty() || bytes.len() > 8 {
+        return None;
+    }
+    let fold = |acc: u64, &b: &u8| acc << 8 | u64::from(b);
+    Some(match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold),
+    })
+}
+
+/// A list of fields describing the layout of a byte buffer.
+///
+/// Rendering a buffer with a schema prints each field on its own lines with its name, type and
+/// decoded value. Bytes not covered by any field are printed without annotation.
+///
+/// Fields of [`Encoding::Redacted`] and [`Encoding::Skipped`] keep secrets and large buffers out of
+/// the output.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::schema::{Encoding, Endian, Schema};
+///
+/// let schema = Schema::new()
+///     .field("version", 0, 1, Encoding::Unsigned(Endian::Little))
+///     .field("length", 1, 2, Encoding::Unsigned(Endian::Big))
+///     .field("name", 3, 2, Encoding::Utf8);
+/// let mut buf: Vec<u8> = vec![];
+/// schema.write(&mut buf, b"\x01\x00\x2ahi\xff").unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "0000 | 01                                                 version: u8 = 1\n\
+///      0001 | 00 2a                                              length: u16be = 42\n\
+///      0003 | 68 69                                              name: utf8 = \"hi\"\n\
+///      0005 | ff\n"
+/// );
+///
+/// let schema = Schema::new()
+///     .field("user", 0, 2, Encoding::Utf8)
+///     .field("password", 2, 4, Encoding::Redacted)
+///     .field("cache", 6, 64, Encoding::Skipped);
+/// let mut buf: Vec<u8> = vec![];
+/// schema.write(&mut buf, &[b'a'; 70]).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "0000 | 61 61                                              user: utf8 = \"aa\"\n\
+///      0002 | ** ** ** **                                        password: [u8; 4] (redacted)\n\
+///      0006 | ..                                                 cache: [u8; 64] (skipped)\n"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Creates an empty schema.
+    pub fn new() -> Schema {
+        Schema::default()
+    }
+
+    /// Adds a field.
+    pub fn field(mut self, name: &str, offset: usize, len: usize, encoding: Encoding) -> Schema {
+        self.push(Field {
+            name: name.to_owned(),
+            offset,
+            len,
+            encoding,
+        });
+        self
+    }
+
+    /// Adds a field, keeping the fields sorted by offset.
+    pub fn push(&mut self, field: Field) {
+        let i = self
+            .fields
+            .iter()
+            .position(|f| f.offset > field.offset)
+            .unwrap_or(self.fields.len());
+        self.fields.insert(i, field);
+    }
+
+    /// Reads a schema from JSON, such as a layout description of an FFI struct kept outside of
+    /// Rust code.
+    ///
+    /// Each field has a `name`, an `offset`, a `size` and a `type`, one of `bytes`, `uint`, `int`,
+    /// `float`, `bfloat16`, `utf8`, `redacted` and `skipped`. Numbers are little-endian unless `endian` is
+    /// `big`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binspect::schema::Schema;
+    ///
+    /// let schema = Schema::from_json(r#"{"fields": [
+    ///     {"name": "magic", "offset": 0, "size": 2, "type": "bytes"},
+    ///     {"name": "length", "offset": 2, "size": 2, "type": "uint", "endian": "big"}
+    /// ]}"#).unwrap();
+    /// let mut buf: Vec<u8> = vec![];
+    /// schema.write(&mut buf, b"\xca\xfe\x01\x00").unwrap();
+    /// assert!(String::from_utf8(buf).unwrap().ends_with("length: u16be = 256\n"));
+    /// ```
+    ///
+    /// Fields ending past the address space are rejected:
+    ///
+    /// ```
+    /// use binspect::schema::Schema;
+    ///
+    /// let json = format!(
+    ///     r#"{{"fields": [{{"name": "x", "offset": {}, "size": 2, "type": "bytes"}}]}}"#,
+    ///     usize::MAX
+    /// );
+    /// assert!(Schema::from_json(&json).is_err());
+    /// ```
+    pub fn from_json(s: &str) -> Result<Schema, ParseError> {
+        let value = json::parse(s)?;
+        json::check_version(&value)?;
+        let mut schema = Schema::new();
+        for field in value.field("fields", Value::as_array)? {
+            let endian = match field.get("endian").map(|v| v.as_str()) {
+                None | Some(Some("little")) => Endian::Little,
+                Some(Some("big")) => Endian::Big,
+                Some(_) => return Err(ParseError::new("invalid \"endian\"")),
+            };
+            let encoding = match field.field("type", Value::as_str)? {
+                "bytes" => Encoding::Bytes,
+                "uint" => Encoding::Unsigned(endian),
+                "int" => Encoding::Signed(endian),
+                "float" => Encoding::Float(endian),
+                "bfloat16" => Encoding::BFloat16(endian),
+                "utf8" => Encoding::Utf8,
+                "redacted" => Encoding::Redacted,
+                "skipped" => Encoding::Skipped,
+                t => return Err(ParseError::new(format!("unknown type {:?}", t))),
+            };
+            let offset = field.field("offset", Value::as_usize)?;
+            let len = field.field("size", Value::as_usize)?;
+            if offset.checked_add(len).is_none() {
+                return Err(ParseError::new("\"offset\" plus \"size\" overflows"));
+            }
+            schema.push(Field {
+                name: field.field("name", Value::as_str)?.to_owned(),
+                offset,
+                len,
+                encoding,
+            });
+        }
+        Ok(schema)
+    }
+
+    /// Returns the fields sorted by offset.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Writes `bytes` annotated with the fields of the schema.
+    pub fn write<W: Write>(&self, mut w: W, bytes: &[u8]) -> Result<(), io::Error> {
+        let mut pos = 0;
+        for field in &self.fields {
+            if field.offset > pos && pos < bytes.len() {
+                let end = field.offset.min(bytes.len());
+                write_segment(&mut w, &bytes[pos..end], pos, None)?;
+            }
+            let start = field.offset.min(bytes.len());
+            let end = field.end().min(bytes.len());
+            let data = &bytes[start..end];
+            let note = field.note(data);
+            write_segment(&mut w, data, field.offset, Some((&note, field.encoding)))?;
+            pos = pos.max(field.end());
+        }
+        if pos < bytes.len() {
+            write_segment(&mut w, &bytes[pos..], pos, None)?;
+        }
+        Ok(())
+    }
+
+    /// Prints `bytes` annotated with the fields of the schema to stdout.
+    pub fn print(&self, bytes: &[u8]) {
+        self.write(crate::capture::output(io::stdout()), bytes)
+            .unwrap()
+    }
+
+    /// Writes the schema as a [Kaitai Struct](https://kaitai.io/) definition with the given `id`,
+    /// to explore dumps in hex tools which read `.ksy` files.
+    ///
+    /// Bytes not covered by any field become `gap_<offset>` fields, and fields overlapping a
+    /// previous one become instances at their position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binspect::schema::{Encoding, Endian, Schema};
+    ///
+    /// let schema = Schema::new()
+    ///     .field("version", 0, 1, Encoding::Unsigned(Endian::Little))
+    ///     .field("Length", 2, 2, Encoding::Unsigned(Endian::Big))
+    ///     .field("name", 4, 8, Encoding::Utf8);
+    /// let mut buf: Vec<u8> = vec![];
+    /// schema.write_kaitai(&mut buf, "packet").unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(buf).unwrap(),
+    ///     "meta:\n  id: packet\n  endian: le\nseq:\n\
+    ///      \x20 - id: version\n    type: u1\n\
+    ///      \x20 - id: gap_1\n    size: 1\n\
+    ///      \x20 - id: length\n    type: u2be\n\
+    ///      \x20 - id: name\n    type: str\n    size: 8\n    encoding: UTF-8\n"
+    /// );
+    /// ```
+    pub fn write_kaitai<W: Write>(&self, mut w: W, id: &str) -> Result<(), io::Error> {
+        writeln!(w, "meta:\n  id: {}\n  endian: le\nseq:", kaitai_id(id))?;
+        let mut pos = 0;
+        let mut instances = vec![];
+        for field in &self.fields {
+            if field.offset < pos {
+                instances.push(field);
+                continue;
+            }
+            if field.offset > pos {
+                writeln!(w, "  - id: gap_{}\n    size: {}", pos, field.offset - pos)?;
+            }
+            writeln!(w, "  - id: {}", kaitai_id(&field.name))?;
+            write_kaitai_type(&mut w, field)?;
+            pos = field.end();
+        }
+        if !instances.is_empty() {
+            writeln!(w, "instances:")?;
+            for field in instances {
+                writeln!(
+                    w,
+                    "  {}:\n    pos: {}",
+                    kaitai_id(&field.name),
+                    field.offset
+                )?;
+                write_kaitai_type(&mut w, field)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the bytes of `value` annotated with the fields of the schema.
+    pub fn write_value<W: Write, T: ?Sized>(&self, w: W, value: &T) -> Result<(), io::Error> {
+        self.write(w, as_bytes(value))
+    }
+
+    /// Prints the bytes of `value` annotated with the fields of the schema to stdout.
+    pub fn print_value<T: ?Sized>(&self, value: &T) {
+        self.print(as_bytes(value))
+    }
+}
+
+/// Returns a valid Kaitai Struct identifier: lowercase letters, digits and underscores, starting
+/// with a letter.
+fn kaitai_id(name: &str) -> String {
+    let mut id: String = name
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ 'a'..='z' | c @ '0'..='9' => c,
+            _ => '_',
+        })
+        .collect();
+    if !id.starts_with(|c: char| c.is_ascii_lowercase()) {
+        id.insert_str(0, "f_");
+    }
+    id
+}
+
+fn write_kaitai_type<W: Write>(w: &mut W, field: &Field) -> Result<(), io::Error> {
+    let endian = |e: Endian| match e {
+        _ if field.len == 1 => "",
+        Endian::Little => "le",
+        Endian::Big => "be",
+    };
+    match (field.encoding, field.len) {
+        (Encoding::Unsigned(e), 1)
+        | (Encoding::Unsigned(e), 2)
+        | (Encoding::Unsigned(e), 4)
+        | (Encoding::Unsigned(e), 8) => writeln!(w, "    type: u{}{}", field.len, endian(e)),
+        (Encoding::Signed(e), 1)
+        | (Encoding::Signed(e), 2)
+        | (Encoding::Signed(e), 4)
+        | (Encoding::Signed(e), 8) => writeln!(w, "    type: s{}{}", field.len, endian(e)),
+        (Encoding::Float(e), 4) | (Encoding::Float(e), 8) => {
+            writeln!(w, "    type: f{}{}", field.len, endian(e))
+        }
+        (Encoding::Utf8, len) => {
+            writeln!(w, "    type: str\n    size: {}\n    encoding: UTF-8", len)
+        }
+        (_, len) => writeln!(w, "    size: {}", len),
+    }
+}
+
+/// Types which describe their own fields, usually implemented with `#[derive(Binspect)]` (requires
+/// the `derive` feature).
+///
+/// [`binspect!`](crate::binspect) and the other dump macros print a map of the fields under the
+/// hex lines of these types, and show [`Encoding::Redacted`] and [`Encoding::Skipped`] fields as
+/// `**` and `..` bytes.
+///
+/// The derive measures the offset and the size of each field on the value, so it works for generic
+/// types and enums, whose fields are named `Variant.field`. Integers and floats are decoded in the
+/// native byte order, and other fields, including those of generic types, are shown as raw bytes.
+/// Fields may be annotated with:
+///
+/// - `#[binspect(redact)]` to hide secrets,
+/// - `#[binspect(skip)]` to hide large buffers,
+/// - `#[binspect(with = path)]` to decode with a `fn(&[u8]) -> Option<String>`, as
+///   [`Decoder`] does,
+/// - `#[binspect(offset = N)]` to check the offset in the tests generated by
+///   `#[binspect(generate_tests(size = N, align = M))]` on the type, which also check the size and
+///   the alignment with [`assert_layout!`](crate::assert_layout).
+///
+/// The offset checks measure fields with `core::ptr::addr_of!`, so they need Rust 1.51 or later.
+/// The test is a function named `__binspect_layout_<Type>` next to the type, so types declared inside
+/// functions get a "cannot test inner items" warning instead, since Rust cannot run such tests.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use binspect::{write_binspect, Binspect};
+///
+/// #[derive(Binspect)]
+/// #[binspect(generate_tests(size = 8, align = 4))]
+/// #[repr(C)]
+/// struct Login {
+///     #[binspect(offset = 0)]
+///     id: u16,
+///     #[binspect(redact)]
+///     pin: [u8; 2],
+///     #[binspect(offset = 4)]
+///     attempts: u32,
+/// }
+///
+/// let login = Login { id: 7, pin: *b"42", attempts: 3 };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, login).unwrap();
+/// # #[cfg(target_endian = "little")]
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | 07 00 ** ** 03 00 00 00\n\
+///      \x20    + 0000..0002 id: u16le = 7\n\
+///      \x20    + 0002..0004 pin: [u8; 2] (redacted)\n\
+///      \x20    + 0004..0008 attempts: u32le = 3\n"
+/// ));
+/// # }
+/// ```
+///
+/// The offsets of the fields of enum variants cannot be checked:
+///
+/// ```compile_fail
+/// #[derive(binspect::Binspect)]
+/// enum Message {
+///     Ping {
+///         #[binspect(offset = 4)]
+///         id: u32,
+///     },
+/// }
+/// ```
+///
+/// Fields can be decoded by functions:
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use binspect::{write_binspect, Binspect};
+///
+/// fn weekday(bytes: &[u8]) -> Option<String> {
+///     let names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+///     names.get(*bytes.first()? as usize).map(|s| s.to_string())
+/// }
+///
+/// #[derive(Binspect)]
+/// #[repr(C)]
+/// struct Alarm {
+///     #[binspect(with = weekday)]
+///     day: u8,
+///     hour: u8,
+/// }
+///
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, Alarm { day: 4, hour: 17 }).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | 04 11\n     + 0000..0001 day: weekday = Fri\n     + 0001..0002 hour: u8 = 17\n"
+/// ));
+/// # }
+/// ```
+///
+/// Generic types need no additional bounds:
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use binspect::{write_binspect, Binspect};
+///
+/// #[derive(Binspect)]
+/// #[repr(C)]
+/// struct Tagged<T> {
+///     tag: u8,
+///     value: T,
+/// }
+///
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, Tagged { tag: 1, value: [2u8; 3] }).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | 01 02 02 02\n     + 0000..0001 tag: u8 = 1\n     + 0001..0004 value: [u8; 3]\n"
+/// ));
+/// # }
+/// ```
+///
+/// The trait can be implemented by hand as well:
+///
+/// ```
+/// use binspect::schema::{Encoding, Endian, Schema};
+/// use binspect::{write_binspect, Binspect};
+///
+/// struct Pair(u8, u8);
+///
+/// impl Binspect for Pair {
+///     fn schema(&self) -> Schema {
+///         Schema::new()
+///             .field("left", 0, 1, Encoding::Unsigned(Endian::native()))
+///             .field("right", 1, 1, Encoding::Skipped)
+///     }
+/// }
+///
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect!(&mut buf, Pair(1, 2)).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | 01 ..\n     + 0000..0001 left: u8 = 1\n     + 0001..0002 right: [u8; 1] (skipped)\n"
+/// ));
+/// ```
+pub trait Binspect {
+    /// Returns the fields of `self`.
+    fn schema(&self) -> Schema;
+}
+
+#[doc(hidden)]
+pub struct SchemaProbe<'a, T: ?Sized>(pub &'a T);
+
+/// Returns the schema of a value if its type implements [`Binspect`]; the dump macros call
+/// `(&SchemaProbe(t)).binspect_schema()`, which resolves to this trait before [`ViaNone`].
+#[doc(hidden)]
+pub trait ViaBinspect {
+    fn binspect_schema(&self) -> Option<Schema>;
+}
+
+impl<'a, T: Binspect + ?Sized> ViaBinspect for SchemaProbe<'a, T> {
+    fn binspect_schema(&self) -> Option<Schema> {
+        Some(self.0.schema())
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaNone {
+    fn binspect_schema(&self) -> Option<Schema>;
+}
+
+impl<'a, T: ?Sized> ViaNone for &SchemaProbe<'a, T> {
+    fn binspect_schema(&self) -> Option<Schema> {
+        None
+    }
+}
+
+/// Creates a [`Schema`] with fields of a value, measured on the value itself.
+///
+/// Offsets and sizes are taken from the value at the place of use, so this works for instances of
+/// generic types as well. Fields are raw bytes unless an encoding is given with `as`.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::schema::{Encoding, Endian};
+/// use binspect::schema_of;
+///
+/// struct Tagged<T> {
+///     tag: u16,
+///     value: T,
+/// }
+///
+/// let v = Tagged { tag: 7, value: [1u8, 2, 3] };
+/// let schema = schema_of!(v, tag as Encoding::Unsigned(Endian::Little), value);
+/// assert_eq!(schema.fields()[0].to_string(), "tag: u16le");
+/// assert_eq!(schema.fields()[1].to_string(), "value: [u8; 3]");
+///
+/// let mut buf: Vec<u8> = vec![];
+/// schema.write_value(&mut buf, &v).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().contains(" 07 00 "));
+///
+/// let pair = (1u8, 2u32);
+/// let schema = schema_of!(pair, 0, 1);
+/// assert_eq!(schema.fields()[0].len, 1);
+/// ```
+#[macro_export]
+macro_rules! schema_of {
+    ($v: expr, $($field: tt $(as $encoding: expr)?),+ $(,)?) => {{
+        let v = &$v;
+        let base = v as *const _ as *const u8 as usize;
+        let mut schema = $crate::schema::Schema::new();
+        $(
+            let f = &v.$field;
+            #[allow(unused_mut, unused_assignments)]
+            let mut encoding = $crate::schema::Encoding::Bytes;
+            $(encoding = $encoding;)?
+            schema = schema.field(
+                stringify!($field),
+                f as *const _ as *const u8 as usize - base,
+                ::std::mem::size_of_val(f),
+                encoding,
+            );
+        )+
+        schema
+    }};
+}
+
+fn write_segment<W: Write>(
+    w: &mut W,
+    bytes: &[u8],
+    offset: usize,
+    note: Option<(&str, Encoding)>,
+) -> Result<(), io::Error> {
+    let encoding = note.map(|(_, encoding)| encoding);
+    if encoding == Some(Encoding::Skipped) {
+        let padding = WIDTH * 3 + 2 - 3;
+        return writeln!(
+            w,
+            "{:04x} | ..{:width$}  {}",
+            offset,
+            "",
+            note.unwrap().0,
+            width = padding
+        );
+    }
+    let mut rows = bytes.chunks(WIDTH);
+    let mut i = 0;
+    loop {
+        let row = rows.next().unwrap_or(&[]);
+        write!(w, "{:04x} |", offset + i)?;
+        let mut width = 0;
+        for (j, x) in row.iter().enumerate() {
+            if j != 0 && j % (WIDTH / 2) == 0 {
+                write!(w, " :")?;
+                width += 2;
+            }
+            if encoding == Some(Encoding::Redacted) {
+                write!(w, " **")?;
+            } else {
+                write!(w, " {:02x}", x)?;
+            }
+            width += 3;
+        }
+        match note {
+            Some((note, _)) if i == 0 => {
+                let padding = WIDTH * 3 + 2 - width;
+                writeln!(w, "{:width$}  {}", "", note, width = padding)?;
+            }
+            _ => writeln!(w)?,
+        }
+        i += row.len();
+        if i >= bytes.len() {
+            return Ok(());
+        }
+    }
+}
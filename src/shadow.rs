@@ -0,0 +1,119 @@
+use std::any::type_name;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::{Deref, DerefMut, Range};
+
+use crate::{as_bytes, limit, DumpInfo, Options, Renderer};
+
+/// A value with a copy of its bytes taken at the last [`commit`](ShadowTracked::commit), to see
+/// which bytes were written since, e.g. to check that a deserializer fills every field.
+///
+/// Bytes written with the value they already had cannot be told from bytes not written; fill the
+/// value with [`POISON`](crate::POISON) bytes first to tell them apart.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::ShadowTracked;
+/// let mut header = ShadowTracked::new([0u8; 8]);
+/// header[..2].copy_from_slice(&[0xca, 0xfe]);
+/// header[6] = 1;
+/// assert_eq!(header.written(), vec![0..2, 6..7]);
+///
+/// let mut buf: Vec<u8> = vec![];
+/// header.write_map(&mut buf).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     "\n0000 | ca fe ~~ ~~ ~~ ~~ 01 ~~\n     + 3 of 8 bytes written\n"
+/// ));
+///
+/// header.commit();
+/// assert!(header.written().is_empty());
+/// ```
+pub struct ShadowTracked<T> {
+    value: T,
+    shadow: Vec<u8>,
+}
+
+impl<T> ShadowTracked<T> {
+    /// Wraps `value` and commits its bytes.
+    pub fn new(value: T) -> ShadowTracked<T> {
+        let shadow = as_bytes(&value).to_vec();
+        ShadowTracked { value, shadow }
+    }
+
+    /// Copies the current bytes of the value to the shadow copy.
+    pub fn commit(&mut self) {
+        self.shadow.clear();
+        self.shadow.extend_from_slice(as_bytes(&self.value));
+    }
+
+    /// Returns the ranges of bytes which differ from the shadow copy.
+    pub fn written(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for (i, (a, b)) in as_bytes(&self.value).iter().zip(&self.shadow).enumerate() {
+            if a == b {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if last.end == i => last.end = i + 1,
+                _ => ranges.push(i..i + 1),
+            }
+        }
+        ranges
+    }
+
+    /// Writes a dump of the value, showing the bytes not written since the last commit as `~~`.
+    pub fn write_map<W: Write>(&self, mut w: W) -> Result<(), io::Error> {
+        let options = Options {
+            shadow: Some(self.shadow.clone()),
+            ..Options::default()
+        };
+        let info = DumpInfo {
+            address: &self.value as *const T as *const u8,
+            type_name: type_name::<T>(),
+            size: mem::size_of::<T>(),
+            source: "ShadowTracked",
+            label: None,
+            file: "",
+            line: 0,
+            column: 0,
+            schema: None,
+            meta: &[],
+        };
+        options.render(&mut w, &info, as_bytes(&self.value))
+    }
+
+    /// Prints a dump of the value to stdout like [`write_map`](ShadowTracked::write_map).
+    pub fn print_map(&self) {
+        limit::limited(io::stdout(), |w| self.write_map(w)).unwrap()
+    }
+
+    /// Returns the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for ShadowTracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for ShadowTracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ShadowTracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShadowTracked")
+            .field("value", &self.value)
+            .field("written", &self.written())
+            .finish()
+    }
+}
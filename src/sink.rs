@@ -0,0 +1,134 @@
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::{Mutex, MutexGuard};
+
+type Sink = Box<dyn Write + Send>;
+
+thread_local! {
+    /// Whether this thread is running the sink, so that dumps printed by the sink itself go to
+    /// stdout and stderr rather than locking it again.
+    static WRITING: Cell<bool> = Cell::new(false);
+}
+
+global! {
+    fn sink() -> &'static Mutex<Option<Sink>> = Mutex::new(None);
+}
+
+/// Sends the dumps of the macros printing to stdout or stderr, such as
+/// [`binspect!`](crate::binspect) and [`ebinspect!`](crate::ebinspect), to `w` instead, e.g. to a
+/// log file of a daemon. Replaces the sink set before, if any.
+///
+/// The sink is shared by all threads. Output redirected by [`with_sink`](crate::with_sink) or
+/// captured by [`capture`](crate::capture) does not reach it, and the `Auto` color choice never
+/// colors it. Dumps printed by the sink itself while it writes go to stdout and stderr, which are
+/// otherwise not locked while a sink is set.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, Write};
+/// use std::sync::{Arc, Mutex};
+///
+/// use binspect::{binspect, remove_sink, set_sink};
+///
+/// #[derive(Clone, Default)]
+/// struct Shared(Arc<Mutex<Vec<u8>>>);
+///
+/// impl Write for Shared {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.lock().unwrap().write(buf)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let log = Shared::default();
+/// set_sink(Box::new(log.clone()));
+/// let v = 0x0102u16;
+/// binspect!(v);
+/// remove_sink();
+/// binspect!(v);
+/// let out = String::from_utf8(log.0.lock().unwrap().clone()).unwrap();
+/// assert_eq!(out.matches(": u16 = v\n0000 | 02 01\n").count(), 1);
+/// ```
+///
+/// A sink may dump as well, e.g. to trace what it is given:
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use binspect::{binspect, remove_sink, set_sink};
+///
+/// struct Tracing;
+///
+/// impl Write for Tracing {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         let len = buf.len();
+///         binspect!(len);
+///         Ok(len)
+///     }
+///
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// set_sink(Box::new(Tracing));
+/// binspect!(1u8);
+/// remove_sink();
+/// ```
+pub fn set_sink(w: Box<dyn Write + Send>) {
+    *lock() = Some(w);
+}
+
+/// Removes the sink set by [`set_sink`] and returns it, so that dumps are printed to stdout and
+/// stderr again.
+pub fn remove_sink() -> Option<Box<dyn Write + Send>> {
+    lock().take()
+}
+
+fn lock() -> MutexGuard<'static, Option<Sink>> {
+    // A sink which panicked is still usable, so the poison is ignored.
+    sink().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Runs `f` with the sink while holding its lock, or returns `None` if no sink is set or this
+/// thread is running it already.
+fn with_sink<T, F: FnOnce(&mut Sink) -> T>(f: F) -> Option<T> {
+    /// Clears [`WRITING`] when the sink returns or panics.
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            WRITING.with(|w| w.set(false));
+        }
+    }
+
+    if WRITING.with(|w| w.get()) {
+        return None;
+    }
+    let mut sink = lock();
+    let w = sink.as_mut()?;
+    WRITING.with(|w| w.set(true));
+    let _reset = Reset;
+    Some(f(w))
+}
+
+/// Returns whether a sink is set and not running on this thread.
+#[cfg(feature = "color")]
+pub(crate) fn is_set() -> bool {
+    !WRITING.with(|w| w.get()) && lock().is_some()
+}
+
+/// Writes all of `buf` to the sink while holding its lock, so that dumps written at once are not
+/// interleaved with those of other threads, or returns `None` if no sink is set.
+pub(crate) fn write(buf: &[u8]) -> Option<Result<usize, io::Error>> {
+    with_sink(|w| w.write_all(buf).map(|_| buf.len()))
+}
+
+/// Flushes the sink, or returns `None` if no sink is set.
+pub(crate) fn flush() -> Option<Result<(), io::Error>> {
+    with_sink(|w| w.flush())
+}
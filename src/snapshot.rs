@@ -0,0 +1,116 @@
+use std::any::type_name;
+use std::fmt;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+use crate::diff::write_diff;
+use crate::{as_bytes, limit};
+
+/// A copy of the bytes of an object, to find which bytes changed later, e.g. the bytes of an
+/// opaque struct mutated by a function.
+///
+/// A snapshot is taken with [`binspect_snapshot!`] or [`Snapshot::of`], and compared with the
+/// object with [`print_diff`](Snapshot::print_diff) or [`write_diff`](Snapshot::write_diff),
+/// which print only the ranges of changed bytes with their old and new values.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_snapshot;
+/// let mut v = (1u32, [0u8; 4]);
+/// let snapshot = binspect_snapshot!(v);
+/// v.1[2] = 0xff;
+/// let mut buf: Vec<u8> = vec![];
+/// assert_eq!(snapshot.write_diff(&mut buf, &v).unwrap(), 1);
+/// assert!(String::from_utf8(buf).unwrap().ends_with(
+///     ": (u32, [u8; 4]) = v (1 bytes changed since snapshot)\n0006 | - 00\n0006 | + ff\n"
+/// ));
+/// ```
+pub struct Snapshot<T: ?Sized> {
+    bytes: Vec<u8>,
+    source: &'static str,
+    phantom: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> Snapshot<T> {
+    /// Copies the bytes of `value`.
+    pub fn of(value: &T) -> Snapshot<T> {
+        Snapshot::with_source(value, "")
+    }
+
+    #[doc(hidden)]
+    pub fn with_source(value: &T, source: &'static str) -> Snapshot<T> {
+        Snapshot {
+            bytes: as_bytes(value).to_vec(),
+            source,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the copied bytes.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Writes the ranges of bytes of `value` which differ from the snapshot, as pairs of `-` and
+    /// `+` lines of the old and the new bytes. Returns the number of changed bytes.
+    pub fn write_diff<W: Write>(&self, mut w: W, value: &T) -> Result<usize, io::Error> {
+        let bytes = as_bytes(value);
+        let changed = (0..self.bytes.len().max(bytes.len()))
+            .filter(|&i| self.bytes.get(i) != bytes.get(i))
+            .count();
+        write!(w, "-----+ {:p}: {}", value, type_name::<T>())?;
+        if !self.source.is_empty() {
+            write!(w, " = {}", self.source)?;
+        }
+        writeln!(w, " ({} bytes changed since snapshot)", changed)?;
+        if self.bytes.len() != bytes.len() {
+            writeln!(w, "     ! size {} -> {}", self.bytes.len(), bytes.len())?;
+        }
+        write_diff(&mut w, &self.bytes, bytes)
+    }
+
+    /// Prints the ranges of bytes of `value` which differ from the snapshot to stdout, like
+    /// [`write_diff`](Snapshot::write_diff).
+    pub fn print_diff(&self, value: &T) {
+        limit::limited(io::stdout(), |w| self.write_diff(w, value).map(|_| ())).unwrap()
+    }
+}
+
+impl<T: ?Sized> Clone for Snapshot<T> {
+    fn clone(&self) -> Snapshot<T> {
+        Snapshot {
+            bytes: self.bytes.clone(),
+            source: self.source,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("type", &type_name::<T>())
+            .field("source", &self.source)
+            .field("bytes", &self.bytes)
+            .finish()
+    }
+}
+
+/// Takes a [`Snapshot`] of an object, whose changes can be printed later.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_snapshot;
+/// let mut v = [1u16, 2, 3];
+/// let snapshot = binspect_snapshot!(v);
+/// v[1] = 7;
+/// snapshot.print_diff(&v);
+/// ```
+#[macro_export]
+macro_rules! binspect_snapshot {
+    ($v: expr) => {
+        $crate::Snapshot::with_source(&$v, stringify!($v))
+    };
+}
@@ -0,0 +1,120 @@
+use std::any::type_name;
+use std::io::{self, Write};
+use std::mem;
+
+use crate::limit;
+use crate::schema::Binspect;
+
+/// Size of the cache lines, as in the cache line report.
+const LINE: usize = 64;
+
+/// Writes a report on how the fields of `items` are laid out, to tell whether turning the slice
+/// of structs (AoS) into a struct of slices (SoA) would help.
+///
+/// For each field of the first element, the report shows its size, its stride, and how many
+/// cache lines a scan of the field over all elements touches, in the slice and in a slice of the
+/// field only. It ends with the number of padding bytes per element, which an SoA layout does not
+/// have.
+///
+/// # Examples
+///
+/// ```
+/// use binspect::schema::{Encoding, Schema};
+/// use binspect::{write_soa_report, Binspect};
+///
+/// #[repr(C, align(64))]
+/// struct Particle {
+///     id: u32,
+///     alive: bool,
+///     position: [f32; 3],
+/// }
+///
+/// impl Binspect for Particle {
+///     fn schema(&self) -> Schema {
+///         Schema::new()
+///             .field("id", 0, 4, Encoding::Bytes)
+///             .field("alive", 4, 1, Encoding::Bytes)
+///             .field("position", 8, 12, Encoding::Bytes)
+///     }
+/// }
+///
+/// let particles: Vec<Particle> = (0..16)
+///     .map(|id| Particle { id, alive: true, position: [0.0; 3] })
+///     .collect();
+/// let mut buf: Vec<u8> = vec![];
+/// write_soa_report(&mut buf, &particles).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// assert!(out.ends_with(
+///     "Particle] x 16, 64 bytes per element\n\
+///      id       :  4 bytes, stride 64, 16 cache lines as AoS, 1 as SoA\n\
+///      alive    :  1 bytes, stride 64, 16 cache lines as AoS, 1 as SoA\n\
+///      position : 12 bytes, stride 64, 16 cache lines as AoS, 3 as SoA\n\
+///      \x20    + 47 of 64 bytes per element are padding\n"
+/// ));
+/// ```
+pub fn write_soa_report<W: Write, T: Binspect>(mut w: W, items: &[T]) -> Result<(), io::Error> {
+    let size = mem::size_of::<T>();
+    writeln!(
+        w,
+        "-----+ {:p}: [{}] x {}, {} bytes per element",
+        items.as_ptr(),
+        type_name::<T>(),
+        items.len(),
+        size
+    )?;
+    let first = match items.first() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    let schema = first.schema();
+    let fields = schema.fields();
+    let name_width = fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+    let size_width = fields
+        .iter()
+        .map(|f| f.len.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let base = items.as_ptr() as usize;
+    for field in fields {
+        let mut aos = 0;
+        let mut last = None;
+        for i in 0..items.len() {
+            let start = base + i * size + field.offset;
+            if field.len == 0 {
+                continue;
+            }
+            for line in start / LINE..=(start + field.len - 1) / LINE {
+                if last.map_or(true, |last| line > last) {
+                    aos += 1;
+                    last = Some(line);
+                }
+            }
+        }
+        let soa = (items.len() * field.len + LINE - 1) / LINE;
+        writeln!(
+            w,
+            "{:nw$} : {:>sw$} bytes, stride {}, {} cache lines as AoS, {} as SoA",
+            field.name,
+            field.len,
+            size,
+            aos,
+            soa,
+            nw = name_width,
+            sw = size_width
+        )?;
+    }
+    let padding = (0..size)
+        .filter(|&i| !fields.iter().any(|f| f.offset <= i && i < f.end()))
+        .count();
+    writeln!(
+        w,
+        "     + {} of {} bytes per element are padding",
+        padding, size
+    )
+}
+
+/// Prints a report on how the fields of `items` are laid out to stdout, like
+/// [`write_soa_report`].
+pub fn print_soa_report<T: Binspect>(items: &[T]) {
+    limit::limited(io::stdout(), |w| write_soa_report(w, items)).unwrap()
+}
@@ -0,0 +1,246 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{write_value, DumpInfo};
+
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// A policy writing large dumps to files, printing only where they were written.
+///
+/// Files are named `binspect-<pid>-<seq>.txt` after the process id and a sequence number, and the
+/// directory is created if it does not exist.
+///
+/// Each file is also listed in `index.txt` in the directory, one line of `key=value` pairs per file
+/// with its name, the call site, the type, the size and the FNV-1a checksum of the bytes:
+///
+/// ```text
+/// file=binspect-4242-0001.txt at=src/main.rs:8:5 type="[u8; 256]" size=256 checksum=fnv1a64:d80ac658736bb725
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options, Spill};
+/// let dir = std::env::temp_dir().join("binspect-spill-example");
+/// let options = Options {
+///     spill: Some(Spill::new(64, &dir)),
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, [0u8; 16]).unwrap();
+/// write_binspect_with!(&mut buf, &options, [0u8; 256]).unwrap();
+/// let out = String::from_utf8(buf).unwrap();
+/// let path = out.lines().last().unwrap().split(" written to ").nth(1).unwrap();
+/// assert!(out.contains("\n0000 | 00 00 00 00 00 00 00 00 : 00 00 00 00 00 00 00 00\n"));
+/// assert!(std::fs::read_to_string(path).unwrap().contains("\n00f0 | 00 00 00 00"));
+/// let index = std::fs::read_to_string(dir.join("index.txt")).unwrap();
+/// assert!(index.contains(" type=\"[u8; 256]\" size=256 checksum=fnv1a64:"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spill {
+    /// Minimum size in bytes of the objects whose dumps are written to files.
+    pub threshold: usize,
+    /// Directory of the files.
+    pub dir: PathBuf,
+    /// Compression of the files.
+    pub compression: Compression,
+}
+
+/// Compression of the files written by [`Spill`].
+///
+/// The extension of the compressed files is `.txt.gz` or `.txt.zst`. The checksum in the index is
+/// always the one of the uncompressed bytes.
+///
+/// All variants exist regardless of the enabled features, but spilling a dump with a compression
+/// whose feature is disabled fails with an error of kind [`io::ErrorKind::Other`].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "gzip")]
+/// # {
+/// # use binspect::{write_binspect_with, Compression, Options, Spill};
+/// let dir = std::env::temp_dir().join("binspect-compression-example");
+/// let options = Options {
+///     spill: Some(Spill {
+///         compression: Compression::Gzip,
+///         ..Spill::new(0, &dir)
+///     }),
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, [0u8; 4096]).unwrap();
+/// assert!(String::from_utf8(buf).unwrap().ends_with(".txt.gz\n"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # }
+/// ```
+///
+/// Without the feature of a compression, dumps which would be spilled are not written:
+///
+/// ```
+/// # #[cfg(not(feature = "zstd"))]
+/// # {
+/// # use binspect::{write_binspect_with, Compression, Options, Spill};
+/// let dir = std::env::temp_dir().join("binspect-disabled-compression-example");
+/// let options = Options {
+///     spill: Some(Spill {
+///         compression: Compression::Zstd,
+///         ..Spill::new(0, &dir)
+///     }),
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// let e = write_binspect_with!(&mut buf, &options, [0u8; 16]).unwrap_err();
+/// assert_eq!(e.to_string(), "binspect was built without the `zstd` feature");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    /// gzip (requires the `gzip` feature).
+    Gzip,
+    /// Zstandard (requires the `zstd` feature).
+    Zstd,
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::None
+    }
+}
+
+enum Encoder {
+    None(BufWriter<File>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd_crate::Encoder<'static, BufWriter<File>>),
+}
+
+impl Encoder {
+    /// Creates the file at `path`, unless the feature of `compression` is disabled.
+    fn new(path: &Path, compression: Compression) -> Result<Encoder, io::Error> {
+        let file = || File::create(path).map(BufWriter::new);
+        Ok(match compression {
+            Compression::None => Encoder::None(file()?),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                file()?,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Encoder::Zstd(zstd_crate::Encoder::new(file()?, 0)?),
+            #[cfg(not(feature = "gzip"))]
+            Compression::Gzip => return Err(disabled("gzip")),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => return Err(disabled("zstd")),
+        })
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Encoder::None(w) => w,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(w) => w,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(w) => w,
+        }
+    }
+
+    // Without compression features, only `Encoder::None` remains.
+    #[allow(clippy::infallible_destructuring_match)]
+    fn finish(self) -> Result<(), io::Error> {
+        let mut file = match self {
+            Encoder::None(w) => w,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(w) => w.finish()?,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(w) => w.finish()?,
+        };
+        file.flush()
+    }
+}
+
+fn extension(compression: Compression) -> &'static str {
+    match compression {
+        Compression::None => "txt",
+        Compression::Gzip => "txt.gz",
+        Compression::Zstd => "txt.zst",
+    }
+}
+
+/// Returns the error of a compression whose feature is disabled.
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn disabled(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        format!("binspect was built without the `{}` feature", feature),
+    )
+}
+
+impl Spill {
+    /// Creates a policy writing dumps of objects of at least `threshold` bytes to `dir`.
+    pub fn new<P: AsRef<Path>>(threshold: usize, dir: P) -> Spill {
+        Spill {
+            threshold,
+            dir: dir.as_ref().to_owned(),
+            compression: Compression::None,
+        }
+    }
+
+    /// Creates a new file in the directory, writes the dump of `bytes` to it with `f`, lists it in
+    /// the index and returns its path.
+    pub(crate) fn create<F>(
+        &self,
+        info: &DumpInfo,
+        bytes: &[u8],
+        f: F,
+    ) -> Result<PathBuf, io::Error>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), io::Error>,
+    {
+        fs::create_dir_all(&self.dir)?;
+        let seq = SEQUENCE.fetch_add(1, Ordering::SeqCst);
+        let name = format!(
+            "binspect-{}-{:04}.{}",
+            process::id(),
+            seq,
+            extension(self.compression)
+        );
+        let path = self.dir.join(&name);
+        let mut encoder = Encoder::new(&path, self.compression)?;
+        f(encoder.writer())?;
+        encoder.finish()?;
+
+        let mut index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join("index.txt"))?;
+        let mut line: Vec<u8> = vec![];
+        write!(line, "file=")?;
+        write_value(&mut line, &name, false)?;
+        write!(line, " at=")?;
+        write_value(&mut line, info.file, false)?;
+        write!(line, ":{}:{} type=", info.line, info.column)?;
+        write_value(&mut line, info.type_name, false)?;
+        writeln!(
+            line,
+            " size={} checksum=fnv1a64:{:016x}",
+            bytes.len(),
+            fnv1a64(bytes)
+        )?;
+        index.write_all(&line)?;
+        Ok(path)
+    }
+}
+
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,103 @@
+use std::any::type_name;
+use std::io::{self, Write};
+
+use crate::{limit, WIDTH};
+
+#[doc(hidden)]
+pub struct Row<'a> {
+    source: &'a str,
+    type_name: &'static str,
+    bytes: &'a [u8],
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn row<'a, T: ?Sized>(t: &'a T, source: &'a str) -> Row<'a> {
+    Row {
+        source,
+        type_name: type_name::<T>(),
+        bytes: crate::as_bytes(t),
+    }
+}
+
+#[doc(hidden)]
+pub fn write_table_internal<W: Write>(mut w: W, rows: &[Row]) -> Result<(), io::Error> {
+    let source_width = rows.iter().map(|r| r.source.len()).max().unwrap_or(0);
+    let type_width = rows.iter().map(|r| r.type_name.len()).max().unwrap_or(0);
+    let size_width = rows
+        .iter()
+        .map(|r| r.bytes.len().to_string().len())
+        .max()
+        .unwrap_or(0);
+    for row in rows {
+        write!(
+            w,
+            "{:sw$} : {:tw$} {:>zw$} |",
+            row.source,
+            row.type_name,
+            row.bytes.len(),
+            sw = source_width,
+            tw = type_width,
+            zw = size_width
+        )?;
+        for x in row.bytes.iter().take(WIDTH) {
+            write!(w, " {:02x}", x)?;
+        }
+        if row.bytes.len() > WIDTH {
+            write!(w, " .. (+{} bytes)", row.bytes.len() - WIDTH)?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+#[inline]
+#[doc(hidden)]
+pub fn print_table_internal(rows: &[Row]) {
+    limit::limited(io::stdout(), |w| write_table_internal(w, rows)).unwrap()
+}
+
+/// Prints several objects to stdout as a table, one object per line with its source, type, size
+/// and bytes.
+///
+/// This is denser than [`binspect!`](crate::binspect) for small objects. Only the first 16 bytes
+/// of each object are shown.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_table;
+/// let s = "ABC";
+/// binspect_table!(1u8, 2u32, s, *s);
+/// ```
+#[macro_export]
+macro_rules! binspect_table {
+    ($($v: expr),+ $(,)?) => {
+        $crate::print_table_internal(&[$($crate::table_row(&$v, stringify!($v))),+])
+    };
+}
+
+/// Writes several objects to a [`Write`](std::io::Write) as a table, as
+/// [`binspect_table!`] does.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_table;
+/// let (x, n, c, big) = (42u8, 0x0102_0304_u32, 'a', [7u8; 20]);
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_table!(&mut buf, x, n, c, big).unwrap();
+/// assert_eq!(
+///     String::from_utf8(buf).unwrap(),
+///     "x   : u8        1 | 2a\n\
+///      n   : u32       4 | 04 03 02 01\n\
+///      c   : char      4 | 61 00 00 00\n\
+///      big : [u8; 20] 20 | 07 07 07 07 07 07 07 07 07 07 07 07 07 07 07 07 .. (+4 bytes)\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! write_binspect_table {
+    ($w: expr, $($v: expr),+ $(,)?) => {
+        $crate::write_table_internal($w, &[$($crate::table_row(&$v, stringify!($v))),+])
+    };
+}
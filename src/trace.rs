@@ -0,0 +1,72 @@
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::DumpInfo;
+
+/// Emits a `tracing` event for a dump, so that it can be located on the timeline of a profiler
+/// or a trace viewer.
+#[cfg(feature = "std")]
+pub(crate) fn emit(info: &DumpInfo) {
+    tracing::info!(
+        target: "binspect",
+        address = %format_args!("{:p}", info.address),
+        r#type = info.type_name,
+        size = info.size as u64,
+        src = info.source,
+        label = info.label.unwrap_or(""),
+        file = info.file,
+        line = info.line,
+        "binspect dump"
+    );
+}
+
+/// The bytes of a dump as a single hex string, e.g. `0a0b0c`, for fields of `tracing` events.
+#[doc(hidden)]
+pub struct HexBody<'a>(pub &'a [u8]);
+
+impl fmt::Display for HexBody<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for x in self.0 {
+            write!(f, "{:02x}", x)?;
+        }
+        Ok(())
+    }
+}
+
+/// Emits a `tracing` event with the dump of an object (requires the `tracing` feature).
+///
+/// The event has the fields `address`, `type_name`, `size`, `src` and `hex`, the bytes as a
+/// single hex string, so that dumps flow into `tracing` subscribers within the current span. The
+/// level is `DEBUG` unless given with `level = ..`.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::trace_binspect;
+/// use tracing::Level;
+///
+/// let header = [0xcau8, 0xfe, 0x00, 0x01];
+/// let span = tracing::info_span!("parse");
+/// let _guard = span.enter();
+/// trace_binspect!(header);
+/// trace_binspect!(header[1..], level = Level::TRACE);
+/// ```
+#[macro_export]
+macro_rules! trace_binspect {
+    ($v: expr) => {
+        $crate::trace_binspect!($v, level = $crate::tracing::Level::DEBUG)
+    };
+    ($v: expr, level = $level: expr) => {
+        match &$v {
+            t => $crate::tracing::event!(
+                $level,
+                address = ?(t as *const _ as *const u8),
+                type_name = $crate::type_name_of(t),
+                size = $crate::as_bytes(t).len() as u64,
+                src = stringify!($v),
+                hex = %$crate::HexBody($crate::as_bytes(t)),
+                "binspect"
+            ),
+        }
+    };
+}
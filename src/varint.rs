@@ -0,0 +1,104 @@
+use std::ops::Range;
+
+/// A sequence of consecutive LEB128 varints in a dump, for
+/// [`Options::varints`](crate::Options::varints).
+///
+/// Each varint is shown at the end of the line it starts on with its extent and value, e.g.
+/// `varint 0002..0004 = 300`.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::{write_binspect_with, Options, Varints};
+/// // A protobuf message with field 1 set to 300.
+/// let message = [0x08u8, 0xac, 0x02];
+/// let options = Options {
+///     varints: vec![Varints::unsigned(0)],
+///     ..Options::default()
+/// };
+/// let mut buf: Vec<u8> = vec![];
+/// write_binspect_with!(&mut buf, &options, message).unwrap();
+/// assert!(String::from_utf8(buf)
+///     .unwrap()
+///     .ends_with("0000 | 08 ac 02                                           varint 0000..0001 = 8, varint 0001..0003 = 300\n"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Varints {
+    /// The offset of the first varint.
+    pub offset: usize,
+    /// The number of varints, or `None` to decode them up to the end of the dump.
+    pub count: Option<usize>,
+    /// Whether the varints are signed LEB128, as in WebAssembly, rather than unsigned as in
+    /// protobuf.
+    pub signed: bool,
+}
+
+impl Varints {
+    /// Creates a sequence of unsigned varints from `offset` up to the end of the dump.
+    pub fn unsigned(offset: usize) -> Varints {
+        Varints {
+            offset,
+            count: None,
+            signed: false,
+        }
+    }
+
+    /// Creates a sequence of signed varints from `offset` up to the end of the dump.
+    pub fn signed(offset: usize) -> Varints {
+        Varints {
+            offset,
+            count: None,
+            signed: true,
+        }
+    }
+}
+
+/// Decodes the varints of `sequences` in `bytes`, which start at offset `base` of the dump.
+/// Returns the extents of the varints as offsets of the dump with their values, or with
+/// `truncated` if their last byte is missing.
+pub(crate) fn decode(
+    bytes: &[u8],
+    base: usize,
+    sequences: &[Varints],
+) -> Vec<(Range<usize>, String)> {
+    let mut decoded = vec![];
+    for sequence in sequences {
+        if sequence.offset < base {
+            continue;
+        }
+        let mut i = sequence.offset - base;
+        let mut n = 0;
+        while i < bytes.len() && sequence.count.map_or(true, |count| n < count) {
+            let start = i;
+            let mut value = 0u64;
+            let mut shift = 0;
+            let mut done = false;
+            while i < bytes.len() && shift < 70 {
+                let b = bytes[i];
+                i += 1;
+                if shift < 64 {
+                    value |= u64::from(b & 0x7f) << shift;
+                }
+                shift += 7;
+                if b & 0x80 == 0 {
+                    if sequence.signed && shift < 64 && b & 0x40 != 0 {
+                        value |= !0 << shift;
+                    }
+                    done = true;
+                    break;
+                }
+            }
+            let shown = match (done, sequence.signed) {
+                (false, _) => "truncated".to_owned(),
+                (true, false) => value.to_string(),
+                (true, true) => (value as i64).to_string(),
+            };
+            decoded.push((base + start..base + i, shown));
+            if !done {
+                break;
+            }
+            n += 1;
+        }
+    }
+    decoded
+}
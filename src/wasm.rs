@@ -0,0 +1,101 @@
+use std::ops::Range;
+use std::slice;
+
+use core::arch::wasm32;
+
+/// Size of a page of linear memory.
+const PAGE: usize = 0x1_0000;
+
+/// Returns the bytes of the linear memory at `range`, whose bounds are offsets as shown by wasm
+/// debuggers and indices of `WebAssembly.Memory` views.
+///
+/// # Safety
+///
+/// The bytes at `range` must be initialized, and must not be mutated or freed while the returned
+/// slice is in use, e.g. through a `&mut` reference, a `static mut` or an allocation which is
+/// deallocated meanwhile.
+///
+/// # Panics
+///
+/// Panics if `range` is not within the current size of the memory, or if it is not empty and
+/// starts at offset 0, which cannot be referenced in Rust.
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::linear_memory;
+/// static DATA: [u8; 4] = *b"wasm";
+/// let offset = &DATA as *const _ as usize;
+/// assert_eq!(unsafe { linear_memory(offset..offset + 4) }, b"wasm");
+/// ```
+pub unsafe fn linear_memory(range: Range<usize>) -> &'static [u8] {
+    if range.start == range.end {
+        return &[];
+    }
+    let size = wasm32::memory_size(0) * PAGE;
+    assert!(
+        0 < range.start && range.start < range.end && range.end <= size,
+        "{:#010x}..{:#010x} is out of linear memory of {:#010x} bytes",
+        range.start,
+        range.end,
+        size
+    );
+    // Linear memory never shrinks, so the bytes stay in bounds.
+    slice::from_raw_parts(range.start as *const u8, range.end - range.start)
+}
+
+/// Prints a range of linear memory to stdout like [`binspect!`](crate::binspect).
+///
+/// # Safety
+///
+/// The macro must be called in an `unsafe` block, and the range must be valid as for
+/// [`linear_memory`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::binspect_memory;
+/// static DATA: [u8; 4] = *b"wasm";
+/// let offset = &DATA as *const _ as usize;
+/// unsafe { binspect_memory!(offset..offset + 4) };
+/// ```
+#[macro_export]
+macro_rules! binspect_memory {
+    ($range: expr) => {{
+        let t = $crate::linear_memory($range);
+        $crate::print_internal(
+            &$crate::record!(t, $range, t, false),
+            &$crate::Options::default(),
+        )
+    }};
+}
+
+/// Writes a range of linear memory to a [`Write`](std::io::Write), as [`binspect_memory!`] does.
+///
+/// # Safety
+///
+/// As for [`binspect_memory!`].
+///
+/// # Examples
+///
+/// ```
+/// # use binspect::write_binspect_memory;
+/// static DATA: [u8; 4] = *b"wasm";
+/// let offset = &DATA as *const _ as usize;
+/// let mut buf: Vec<u8> = vec![];
+/// unsafe { write_binspect_memory!(&mut buf, offset..offset + 4) }.unwrap();
+/// assert!(String::from_utf8(buf)
+///     .unwrap()
+///     .starts_with(&format!("-----+ {:#010x}: [u8] = ", offset)));
+/// ```
+#[macro_export]
+macro_rules! write_binspect_memory {
+    ($w: expr, $range: expr) => {{
+        let t = $crate::linear_memory($range);
+        $crate::write_internal(
+            $w,
+            &$crate::record!(t, $range, t, false),
+            &$crate::Options::default(),
+        )
+    }};
+}
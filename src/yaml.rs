@@ -0,0 +1,66 @@
+use std::io::{self, Write};
+use std::ops::Range;
+
+use crate::address::Address;
+use crate::json::{write_quoted, FORMAT_VERSION};
+use crate::{hidden, is_masked, is_unreadable, region, DumpInfo, Options};
+
+/// Writes a dump as a YAML document.
+pub(crate) fn write_yaml<W: Write>(
+    mut w: W,
+    info: &DumpInfo,
+    bytes: &[u8],
+    options: &Options,
+    unreadable: &[Range<usize>],
+) -> Result<(), io::Error> {
+    writeln!(w, "---")?;
+    let address = Address(info.address, options);
+    writeln!(w, "addr: \"{}\"", address)?;
+    if options.regions {
+        if let Some(region) = region::find(address.0 as usize) {
+            write!(w, "region: ")?;
+            write_quoted(&mut w, &region)?;
+            writeln!(w)?;
+        }
+    }
+    write!(w, "type: ")?;
+    write_quoted(&mut w, info.type_name)?;
+    write!(w, "\nsize: {}\nsrc: ", info.size)?;
+    write_quoted(&mut w, info.source)?;
+    if let Some(label) = info.label {
+        write!(w, "\nlabel: ")?;
+        write_quoted(&mut w, label)?;
+    }
+    if !info.meta.is_empty() {
+        write!(w, "\nmeta:")?;
+        for (key, value) in info.meta {
+            write!(w, "\n  ")?;
+            write_quoted(&mut w, key)?;
+            write!(w, ": ")?;
+            write_quoted(&mut w, &value.to_string())?;
+        }
+    }
+    write!(w, "\nfile: ")?;
+    write_quoted(&mut w, info.file)?;
+    write!(
+        w,
+        "\nline: {}\ncolumn: {}\nversion: {}\nbytes: \"",
+        info.line, info.column, FORMAT_VERSION
+    )?;
+    for (i, x) in bytes.iter().enumerate() {
+        if is_unreadable(unreadable, i) {
+            write!(w, "??")?;
+        } else if let Some(s) = hidden(info.schema, i) {
+            write!(w, "{}", s)?;
+        } else if options.placeholders && is_masked(bytes, i) {
+            write!(w, "##")?;
+        } else {
+            let x = match &options.mask {
+                Some(mask) => mask.apply(i, *x),
+                None => *x,
+            };
+            write!(w, "{:02x}", x)?;
+        }
+    }
+    writeln!(w, "\"")
+}
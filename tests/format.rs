@@ -0,0 +1,257 @@
+//! Property tests of the text format: dumps parse back to their bytes, lines are bounded, and
+//! rendering does not panic for any bytes and options.
+
+#![cfg(feature = "std")]
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use binspect::{
+    hexdump, parse_hex, write_binspect_with, write_hex, AddressFormat, Fixed, Format, Header,
+    HeaderColumn, Lane, Mask, Options, SequentialIds, TagScheme, Varints,
+};
+#[cfg(feature = "color")]
+use binspect::{ColorChoice, ColorTheme};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..300)
+}
+
+fn header() -> impl Strategy<Value = Header> {
+    prop_oneof![
+        Just(Header::Classic),
+        Just(Header::Compact),
+        Just(Header::Verbose),
+        Just(Header::KeyValue),
+    ]
+}
+
+fn format() -> impl Strategy<Value = Format> {
+    prop_oneof![
+        Just(Format::Text),
+        Just(Format::Yaml),
+        Just(Format::Json),
+        option::of(0..200usize).prop_map(Format::Bitmap),
+        any::<u8>().prop_map(Format::Braille),
+    ]
+}
+
+fn lane() -> impl Strategy<Value = Lane> {
+    prop_oneof![
+        Just(Lane::U8),
+        Just(Lane::I8),
+        Just(Lane::U16),
+        Just(Lane::I16),
+        Just(Lane::U32),
+        Just(Lane::I32),
+        Just(Lane::U64),
+        Just(Lane::I64),
+        Just(Lane::F16),
+        Just(Lane::BF16),
+        Just(Lane::F32),
+        Just(Lane::F64),
+        (any::<usize>(), any::<bool>(), any::<i64>(), any::<u64>()).prop_map(
+            |(size, signed, numerator, denominator)| {
+                let fixed = Fixed {
+                    signed,
+                    numerator,
+                    denominator,
+                };
+                Lane::Fixed(size, fixed)
+            }
+        ),
+    ]
+}
+
+fn address() -> impl Strategy<Value = AddressFormat> {
+    prop_oneof![
+        Just(AddressFormat::Full),
+        Just(AddressFormat::Low16),
+        Just(AddressFormat::Module),
+        Just(AddressFormat::Offset),
+        Just(AddressFormat::Scrubbed),
+        Just(AddressFormat::Sequential(SequentialIds::new())),
+        vec(("[a-z]{0,4}", any::<usize>()), 0..3).prop_map(AddressFormat::Relative),
+        Just(AddressFormat::Custom(Arc::new(
+            |_, f| f.write_str("\x1b[1mobj\x1b[0m")
+        ))),
+    ]
+}
+
+fn varints() -> impl Strategy<Value = Varints> {
+    (0..400usize, option::of(0..100usize), any::<bool>()).prop_map(|(offset, count, signed)| {
+        Varints {
+            offset,
+            count,
+            signed,
+        }
+    })
+}
+
+fn mask() -> impl Strategy<Value = Mask> {
+    prop_oneof![
+        vec(any::<u8>(), 0..4).prop_map(Mask::Xor),
+        vec(any::<u8>(), 0..4).prop_map(Mask::And),
+    ]
+}
+
+fn range() -> impl Strategy<Value = Range<usize>> {
+    (0..400usize, 0..400usize).prop_map(|(start, end)| start..end)
+}
+
+fn tags() -> impl Strategy<Value = TagScheme> {
+    (0..80u32, 0..80u32).prop_map(|(low_bits, high_bits)| TagScheme {
+        low_bits,
+        high_bits,
+    })
+}
+
+#[cfg(feature = "color")]
+fn color(options: Options) -> impl Strategy<Value = Options> {
+    (
+        prop_oneof![
+            Just(ColorChoice::Auto),
+            Just(ColorChoice::Always),
+            Just(ColorChoice::Never),
+        ],
+        prop_oneof![Just(ColorTheme::Class), Just(ColorTheme::Heatmap)],
+    )
+        .prop_map(move |(color, color_theme)| Options {
+            color,
+            color_theme,
+            ..options.clone()
+        })
+}
+
+#[cfg(not(feature = "color"))]
+fn color(options: Options) -> impl Strategy<Value = Options> {
+    Just(options)
+}
+
+/// Any options which render without side effects, i.e. without spilling to files.
+fn options() -> impl Strategy<Value = Options> {
+    let flags = vec(any::<bool>(), 14);
+    let values = (
+        header(),
+        format(),
+        option::of(lane()),
+        vec(varints(), 0..3),
+        address(),
+        option::of(tags()),
+        option::of(mask()),
+        option::of(range()),
+        option::of(any::<u8>()),
+        option::of(bytes()),
+        option::of("[ -~]{0,8}"),
+    );
+    (flags, values)
+        .prop_map(
+            |(
+                f,
+                (header, format, lanes, varints, address, tags, mask, zoom, poison, shadow, title),
+            )| {
+                Options {
+                    absolute: f[0],
+                    align_rows: f[1],
+                    placeholders: f[2],
+                    header,
+                    location: f[3],
+                    align_headers: if f[4] {
+                        Some(HeaderColumn::new())
+                    } else {
+                        None
+                    },
+                    thread: f[5],
+                    format,
+                    ascii: f[6],
+                    lanes,
+                    varints,
+                    collapsed: f[7],
+                    address,
+                    regions: f[8],
+                    pointer_hints: f[9],
+                    tags,
+                    word_reads: f[10],
+                    mask,
+                    zoom,
+                    legend: f[11],
+                    padding: f[12],
+                    poison,
+                    shadow,
+                    timestamps: f[13],
+                    title,
+                    ..Options::default()
+                }
+            },
+        )
+        .prop_flat_map(color)
+}
+
+/// Options which only decorate the text format, so that its dumps parse back to their bytes.
+fn decorations() -> impl Strategy<Value = Options> {
+    (
+        prop_oneof![Just(Header::Classic), Just(Header::Verbose)],
+        vec(any::<bool>(), 5),
+    )
+        .prop_map(|(header, f)| Options {
+            header,
+            ascii: f[0],
+            location: f[1],
+            thread: f[2],
+            align_headers: if f[3] {
+                Some(HeaderColumn::new())
+            } else {
+                None
+            },
+            timestamps: f[4],
+            ..Options::default()
+        })
+}
+
+proptest! {
+    #[test]
+    fn write_hex_round_trips(bytes in bytes()) {
+        let mut s = String::new();
+        write_hex(&mut s, &bytes).unwrap();
+        prop_assert_eq!(parse_hex(&s).unwrap(), bytes);
+        prop_assert!(s.lines().all(|line| line.chars().count() <= 56));
+    }
+
+    #[test]
+    fn hexdump_round_trips(bytes in bytes(), ascii in any::<bool>()) {
+        let options = Options {
+            ascii,
+            ..Options::default()
+        };
+        let mut buf: Vec<u8> = vec![];
+        hexdump(&mut buf, &bytes, &options).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        prop_assert_eq!(parse_hex(&s).unwrap(), bytes);
+        let max = if ascii { 74 } else { 56 };
+        prop_assert!(s.lines().all(|line| line.chars().count() <= max));
+    }
+
+    #[test]
+    fn decorated_dumps_round_trip(bytes in bytes(), options in decorations()) {
+        let mut buf: Vec<u8> = vec![];
+        write_binspect_with!(&mut buf, &options, *bytes).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        prop_assert_eq!(parse_hex(&s).unwrap(), bytes);
+    }
+
+    #[test]
+    fn rendering_does_not_panic(bytes in bytes(), options in options()) {
+        let mut buf: Vec<u8> = vec![];
+        let _ = write_binspect_with!(&mut buf, &options, *bytes);
+        let mut buf: Vec<u8> = vec![];
+        let _ = hexdump(&mut buf, &bytes, &options);
+    }
+
+    #[test]
+    fn parse_hex_does_not_panic(s in "([0-9a-f]{0,5}( \\|)?( [0-9a-f :]{0,3}){0,18}\n?){0,4}") {
+        let _ = parse_hex(&s);
+    }
+}